@@ -0,0 +1,168 @@
+//!
+//! Benchmark matching a transaction against an increasing number of filters, one at a time and
+//! through a [`CompiledFilterSet`].
+//!
+//! Run with `cargo bench --features bench`.
+//!
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use croncat_indexer::indexer::config::filter::{
+    AttributeFilter, CompiledFilterSet, Filter, FilterCombinator, FilterKind,
+};
+use tendermint::abci;
+use tendermint_rpc::endpoint::tx;
+
+fn event(type_str: &str, attributes: &[(&str, &str)]) -> abci::Event {
+    abci::Event {
+        type_str: type_str.to_string(),
+        attributes: attributes
+            .iter()
+            .map(|(key, value)| abci::tag::Tag {
+                key: key.parse().unwrap(),
+                value: value.parse().unwrap(),
+            })
+            .collect(),
+    }
+}
+
+///
+/// The events of a typical croncat `proxy_call`, a contract execution with fees and transfers.
+///
+fn proxy_call_events() -> Vec<abci::Event> {
+    let contract = "juno1mc4wfy9unvy2mwx7dskjqhh6v7qta3vqsxmkayclg4c2jude76es0jcp38";
+    let agent = "juno1yhqft6d2msmzpugdjtawsgdlwvgq3samajy9jq";
+
+    vec![
+        event(
+            "coin_spent",
+            &[("spender", agent), ("amount", "4000ujunox")],
+        ),
+        event(
+            "coin_received",
+            &[
+                ("receiver", "juno17xpfvakm2amg962yls6f84z3kell8c5lxtqmvp"),
+                ("amount", "4000ujunox"),
+            ],
+        ),
+        event(
+            "transfer",
+            &[
+                ("recipient", "juno17xpfvakm2amg962yls6f84z3kell8c5lxtqmvp"),
+                ("sender", agent),
+                ("amount", "4000ujunox"),
+            ],
+        ),
+        event("message", &[("sender", agent)]),
+        event("tx", &[("fee", "4000ujunox"), ("fee_payer", agent)]),
+        event(
+            "tx",
+            &[("acc_seq", "juno1yhqft6d2msmzpugdjtawsgdlwvgq3samajy9jq/42")],
+        ),
+        event(
+            "message",
+            &[
+                ("action", "/cosmwasm.wasm.v1.MsgExecuteContract"),
+                ("module", "wasm"),
+                ("sender", agent),
+            ],
+        ),
+        event("execute", &[("_contract_address", contract)]),
+        event(
+            "wasm",
+            &[
+                ("_contract_address", contract),
+                ("method", "proxy_call"),
+                ("agent", agent),
+                (
+                    "task_hash",
+                    "junox:0b7f4e1ef8c1f5ca3d5b9a6e47f4ddeb3fa4d2bfbd0ae9b7c8d12c1d8c6f1e32",
+                ),
+                ("task_version", "0.1"),
+            ],
+        ),
+    ]
+}
+
+///
+/// A successful transaction with the events of a `proxy_call`.
+///
+fn proxy_call_transaction() -> tx::Response {
+    let mut tx: tx::Response = serde_json::from_value(serde_json::json!({
+        "hash": "0B7F4E1EF8C1F5CA3D5B9A6E47F4DDEB3FA4D2BFBD0AE9B7C8D12C1D8C6F1E32",
+        "height": "1",
+        "index": 0,
+        "tx_result": {
+            "code": 0,
+            "data": null,
+            "log": "",
+            "info": "",
+            "gas_wanted": "0",
+            "gas_used": "0",
+            "events": [],
+            "codespace": ""
+        },
+        "tx": "Y3JvbmNhdA==",
+    }))
+    .unwrap();
+    tx.tx_result.events = proxy_call_events();
+    tx
+}
+
+///
+/// Filters on contract addresses that don't match, ending with one that matches the events.
+///
+fn filters(count: usize) -> Vec<Filter> {
+    (0..count)
+        .map(|index| {
+            let contract = if index + 1 == count {
+                "^juno1mc4wfy9unvy2mwx7dskjqhh6v7qta3vqsxmkayclg4c2jude76es0jcp38$".to_string()
+            } else {
+                format!("^juno1contract{}$", index)
+            };
+
+            Filter {
+                name: None,
+                kind: FilterKind::Event,
+                type_str: "^wasm$".try_into().unwrap(),
+                negate: false,
+                code: None,
+                attributes: vec![
+                    AttributeFilter {
+                        key: "^_contract_address$".try_into().unwrap(),
+                        value: Some(contract.as_str().try_into().unwrap()),
+                        negate: false,
+                    },
+                    AttributeFilter {
+                        key: "^method$".try_into().unwrap(),
+                        value: Some("^proxy_call$".try_into().unwrap()),
+                        negate: false,
+                    },
+                ],
+            }
+        })
+        .collect()
+}
+
+fn filter_matching(c: &mut Criterion) {
+    let tx = proxy_call_transaction();
+    let mut group = c.benchmark_group("filter_matching");
+
+    // Only the last filter matches, so every filter is tried against every event.
+    for count in [1, 10, 50] {
+        let filters = filters(count);
+        group.bench_with_input(BenchmarkId::new("naive", count), &filters, |b, filters| {
+            b.iter(|| FilterCombinator::Any.matches(black_box(filters), black_box(&tx)))
+        });
+
+        let filter_set = CompiledFilterSet::new(&filters, FilterCombinator::Any).unwrap();
+        group.bench_with_input(
+            BenchmarkId::new("compiled", count),
+            &filter_set,
+            |b, filter_set| b.iter(|| black_box(filter_set).matches(black_box(&tx))),
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, filter_matching);
+criterion_main!(benches);
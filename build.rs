@@ -0,0 +1,33 @@
+//!
+//! Record the git commit and rustc version the indexer is built with, for `--version`.
+//!
+use std::env;
+use std::process::Command;
+
+///
+/// The trimmed output of a command, if it ran successfully.
+///
+fn output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn main() {
+    let commit =
+        output("git", &["rev-parse", "--short", "HEAD"]).unwrap_or_else(|| "unknown".into());
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".into());
+    let rustc_version = output(&rustc, &["--version"]).unwrap_or_else(|| "unknown rustc".into());
+
+    println!("cargo:rustc-env=CRONCAT_INDEXER_GIT_COMMIT={}", commit);
+    println!(
+        "cargo:rustc-env=CRONCAT_INDEXER_RUSTC_VERSION={}",
+        rustc_version
+    );
+
+    // Pick up new commits and checkouts, building from a source archive reruns every time.
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+}
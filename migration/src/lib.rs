@@ -2,6 +2,17 @@ pub use sea_orm_migration::prelude::*;
 
 mod m20221011_000001_create_block_table;
 mod m20221012_141605_create_transaction_table;
+mod m20221101_000001_create_skipped_block_table;
+mod m20221102_000001_add_block_header_fields;
+mod m20221103_000001_add_transaction_hash_height_index;
+mod m20221104_000001_add_transaction_fee_and_signer;
+mod m20221105_000001_add_block_gas_totals;
+mod m20221106_000001_create_backfill_job_table;
+mod m20221107_000001_add_block_source;
+mod m20221108_000001_add_block_signatures;
+mod m20221109_000001_create_transaction_event_table;
+mod m20221110_000001_create_genesis_backfill_table;
+mod m20221111_000001_make_transaction_events_nullable;
 
 pub struct Migrator;
 
@@ -11,6 +22,17 @@ impl MigratorTrait for Migrator {
         vec![
             Box::new(m20221011_000001_create_block_table::Migration),
             Box::new(m20221012_141605_create_transaction_table::Migration),
+            Box::new(m20221101_000001_create_skipped_block_table::Migration),
+            Box::new(m20221102_000001_add_block_header_fields::Migration),
+            Box::new(m20221103_000001_add_transaction_hash_height_index::Migration),
+            Box::new(m20221104_000001_add_transaction_fee_and_signer::Migration),
+            Box::new(m20221105_000001_add_block_gas_totals::Migration),
+            Box::new(m20221106_000001_create_backfill_job_table::Migration),
+            Box::new(m20221107_000001_add_block_source::Migration),
+            Box::new(m20221108_000001_add_block_signatures::Migration),
+            Box::new(m20221109_000001_create_transaction_event_table::Migration),
+            Box::new(m20221110_000001_create_genesis_backfill_table::Migration),
+            Box::new(m20221111_000001_make_transaction_events_nullable::Migration),
         ]
     }
 }
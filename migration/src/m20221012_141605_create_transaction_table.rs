@@ -1,4 +1,5 @@
 use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::DbBackend;
 
 use crate::m20221011_000001_create_block_table::Block;
 
@@ -8,6 +9,13 @@ pub struct Migration;
 #[async_trait::async_trait]
 impl MigrationTrait for Migration {
     async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite has no binary JSON type, events are stored as JSON text instead.
+        let mut events = ColumnDef::new(Transaction::Events);
+        match manager.get_database_backend() {
+            DbBackend::Sqlite => events.json(),
+            _ => events.json_binary(),
+        };
+
         manager
             .create_table(
                 Table::create()
@@ -29,7 +37,7 @@ impl MigrationTrait for Migration {
                     .col(ColumnDef::new(Transaction::Code).integer().not_null())
                     .col(ColumnDef::new(Transaction::GasWanted).string().not_null())
                     .col(ColumnDef::new(Transaction::GasUsed).string().not_null())
-                    .col(ColumnDef::new(Transaction::Events).json_binary().not_null())
+                    .col(events.not_null())
                     .col(ColumnDef::new(Transaction::Log).text().not_null())
                     .col(ColumnDef::new(Transaction::Info).text().not_null())
                     .foreign_key(
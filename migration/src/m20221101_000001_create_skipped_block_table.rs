@@ -0,0 +1,49 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(SkippedBlock::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(SkippedBlock::Height)
+                            .big_unsigned()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(SkippedBlock::Time).timestamp().not_null())
+                    .col(
+                        ColumnDef::new(SkippedBlock::ChainId)
+                            .string_len(32)
+                            .not_null(),
+                    )
+                    .primary_key(
+                        index::Index::create()
+                            .col(SkippedBlock::Height)
+                            .col(SkippedBlock::ChainId),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(SkippedBlock::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum SkippedBlock {
+    Table,
+    Height,
+    Time,
+    ChainId,
+}
@@ -0,0 +1,61 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite only supports a single change per ALTER TABLE statement.
+        for mut column in [
+            ColumnDef::new(Block::ProposerAddress)
+                .string_len(40)
+                .null()
+                .to_owned(),
+            ColumnDef::new(Block::AppHash).string().null().to_owned(),
+            ColumnDef::new(Block::LastCommitHash)
+                .string_len(64)
+                .null()
+                .to_owned(),
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Block::Table)
+                        .add_column(&mut column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [
+            Block::ProposerAddress,
+            Block::AppHash,
+            Block::LastCommitHash,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Block::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Block {
+    Table,
+    ProposerAddress,
+    AppHash,
+    LastCommitHash,
+}
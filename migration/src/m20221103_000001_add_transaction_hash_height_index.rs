@@ -0,0 +1,67 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, DbBackend, Statement};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Remove duplicates left behind by retried inserts, keeping a single row per transaction.
+        let backend = manager.get_database_backend();
+        let deduplicate = match backend {
+            DbBackend::Sqlite => {
+                r#"
+                DELETE FROM "transaction"
+                WHERE rowid NOT IN (
+                    SELECT min(rowid) FROM "transaction" GROUP BY hash, height
+                )
+                "#
+            }
+            _ => {
+                r#"
+                DELETE FROM "transaction" AS duplicate
+                USING "transaction" AS original
+                WHERE duplicate.hash = original.hash
+                    AND duplicate.height = original.height
+                    AND duplicate.id > original.id
+                "#
+            }
+        };
+        manager
+            .get_connection()
+            .execute(Statement::from_string(backend, deduplicate.to_owned()))
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-transaction-hash-height")
+                    .table(Transaction::Table)
+                    .col(Transaction::Hash)
+                    .col(Transaction::Height)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-transaction-hash-height")
+                    .table(Transaction::Table)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Transaction {
+    Table,
+    Height,
+    Hash,
+}
@@ -0,0 +1,64 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite only supports a single change per ALTER TABLE statement.
+        for mut column in [
+            ColumnDef::new(Transaction::FeeAmount)
+                .string()
+                .null()
+                .to_owned(),
+            ColumnDef::new(Transaction::FeeDenom)
+                .string()
+                .null()
+                .to_owned(),
+            ColumnDef::new(Transaction::Signer)
+                .string()
+                .null()
+                .to_owned(),
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Transaction::Table)
+                        .add_column(&mut column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [
+            Transaction::FeeAmount,
+            Transaction::FeeDenom,
+            Transaction::Signer,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Transaction::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Transaction {
+    Table,
+    FeeAmount,
+    FeeDenom,
+    Signer,
+}
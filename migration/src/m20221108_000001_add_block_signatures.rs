@@ -0,0 +1,104 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite only supports a single change per ALTER TABLE statement.
+        for mut column in [
+            ColumnDef::new(Block::LastCommitSignatures)
+                .integer()
+                .null()
+                .to_owned(),
+            ColumnDef::new(Block::LastCommitValidators)
+                .integer()
+                .null()
+                .to_owned(),
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Block::Table)
+                        .add_column(&mut column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(BlockSignature::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(BlockSignature::Height)
+                            .big_unsigned()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(BlockSignature::ChainId)
+                            .string_len(32)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(BlockSignature::ValidatorAddress)
+                            .string_len(40)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(BlockSignature::BlockIdFlag)
+                            .string_len(16)
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(BlockSignature::Time).timestamp().not_null())
+                    .primary_key(
+                        index::Index::create()
+                            .col(BlockSignature::Height)
+                            .col(BlockSignature::ChainId)
+                            .col(BlockSignature::ValidatorAddress),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(BlockSignature::Table).to_owned())
+            .await?;
+
+        for column in [Block::LastCommitSignatures, Block::LastCommitValidators] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Block::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Block {
+    Table,
+    LastCommitSignatures,
+    LastCommitValidators,
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum BlockSignature {
+    Table,
+    Height,
+    ChainId,
+    ValidatorAddress,
+    BlockIdFlag,
+    Time,
+}
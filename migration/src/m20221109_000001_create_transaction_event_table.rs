@@ -0,0 +1,99 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(TransactionEvent::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(TransactionEvent::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(TransactionEvent::TransactionId)
+                            .uuid()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(TransactionEvent::Height)
+                            .big_unsigned()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(TransactionEvent::EventIndex)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(TransactionEvent::EventType)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(TransactionEvent::Sender).string().null())
+                    .col(ColumnDef::new(TransactionEvent::Recipient).string().null())
+                    .col(ColumnDef::new(TransactionEvent::Amount).text().null())
+                    .col(
+                        ColumnDef::new(TransactionEvent::ContractAddress)
+                            .string()
+                            .null(),
+                    )
+                    // Deleting a transaction, when pruning or replacing a reorged block, removes its events.
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-transaction_id")
+                            .from(TransactionEvent::Table, TransactionEvent::TransactionId)
+                            .to(Transaction::Table, Transaction::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-transaction_event-transaction_id")
+                    .table(TransactionEvent::Table)
+                    .col(TransactionEvent::TransactionId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(TransactionEvent::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Transaction {
+    Table,
+    Id,
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum TransactionEvent {
+    Table,
+    Id,
+    TransactionId,
+    Height,
+    EventIndex,
+    EventType,
+    Sender,
+    Recipient,
+    Amount,
+    ContractAddress,
+}
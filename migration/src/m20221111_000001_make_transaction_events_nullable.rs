@@ -0,0 +1,47 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, DbBackend, Statement};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite can't drop a NOT NULL constraint without rebuilding the table, the indexer stores
+        // an empty array there instead.
+        let backend = manager.get_database_backend();
+        if backend == DbBackend::Sqlite {
+            return Ok(());
+        }
+
+        manager
+            .get_connection()
+            .execute(Statement::from_string(
+                backend,
+                r#"ALTER TABLE "transaction" ALTER COLUMN events DROP NOT NULL"#.to_owned(),
+            ))
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let backend = manager.get_database_backend();
+        if backend == DbBackend::Sqlite {
+            return Ok(());
+        }
+
+        // Transactions indexed without their JSON events get an empty array back.
+        let connection = manager.get_connection();
+        for statement in [
+            r#"UPDATE "transaction" SET events = '[]'::jsonb WHERE events IS NULL"#,
+            r#"ALTER TABLE "transaction" ALTER COLUMN events SET NOT NULL"#,
+        ] {
+            connection
+                .execute(Statement::from_string(backend, statement.to_owned()))
+                .await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,255 @@
+use structopt::StructOpt;
+
+/// Index the chain, get information about croncat contracts and tasks!
+#[derive(Debug, StructOpt)]
+#[structopt(name = "croncat-indexer", long_version = crate::env::LONG_VERSION)]
+pub struct Opt {
+    /// Log what would be indexed instead of writing it to the database.
+    #[structopt(long)]
+    pub dry_run: bool,
+    /// Only run the configuration with this name or chain id.
+    #[structopt(long)]
+    pub only: Option<String>,
+    #[structopt(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, PartialEq, Eq, StructOpt)]
+pub enum Command {
+    /// Run every configured indexer (the default).
+    Run,
+    /// Backfill the gaps of every configured chain, then exit.
+    Backfill,
+    /// Delete and index again a range of heights, using the configuration of the chain.
+    Reindex {
+        /// The chain id of the configuration to use.
+        #[structopt(long)]
+        chain_id: String,
+        /// The first height to reindex.
+        #[structopt(long)]
+        from: i64,
+        /// The last height to reindex.
+        #[structopt(long)]
+        to: i64,
+        /// Only report what would be reindexed.
+        #[structopt(long)]
+        dry_run: bool,
+    },
+    /// Delete the blocks and transactions of a chain older than a number of days.
+    Prune {
+        /// The chain id to prune.
+        #[structopt(long)]
+        chain_id: String,
+        /// The number of days of data to keep.
+        #[structopt(long)]
+        older_than: u64,
+    },
+    /// Report the gaps of a chain without indexing them.
+    Gaps {
+        /// The chain id to report on.
+        #[structopt(long)]
+        chain_id: String,
+        /// The number of days to look back for gaps, 0 for the whole history.
+        #[structopt(long, default_value = "7")]
+        days: i64,
+    },
+    /// Compare the hashes of the stored blocks of a chain with the blocks fetched from rpc.
+    Verify {
+        /// The chain id of the configuration to use.
+        #[structopt(long)]
+        chain_id: String,
+        /// The first height to verify.
+        #[structopt(long)]
+        from: i64,
+        /// The last height to verify.
+        #[structopt(long)]
+        to: i64,
+        /// The maximum number of blocks fetched per second.
+        #[structopt(long, default_value = "10")]
+        rate: u32,
+    },
+    /// Print the stored block of a chain at a height and its transactions as JSON.
+    Show {
+        /// The chain id of the block.
+        #[structopt(long)]
+        chain_id: String,
+        /// The height of the block.
+        #[structopt(long)]
+        height: i64,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opt_reindex() {
+        let opt = Opt::from_iter_safe([
+            "croncat-indexer",
+            "reindex",
+            "--chain-id",
+            "uni-5",
+            "--from",
+            "10",
+            "--to",
+            "20",
+            "--dry-run",
+        ])
+        .unwrap();
+
+        assert_eq!(
+            opt.command,
+            Some(Command::Reindex {
+                chain_id: "uni-5".to_string(),
+                from: 10,
+                to: 20,
+                dry_run: true,
+            })
+        );
+    }
+
+    #[test]
+    fn opt_version() {
+        let err = Opt::from_iter_safe(["croncat-indexer", "--version"]).unwrap_err();
+        assert_eq!(err.kind, structopt::clap::ErrorKind::VersionDisplayed);
+
+        assert!(crate::env::LONG_VERSION.starts_with(env!("CARGO_PKG_VERSION")));
+        assert!(crate::env::LONG_VERSION.contains("rustc"));
+    }
+
+    #[test]
+    fn opt_default() {
+        let opt = Opt::from_iter_safe(["croncat-indexer"]).unwrap();
+        assert_eq!(opt.command, None);
+        assert!(!opt.dry_run);
+    }
+
+    #[test]
+    fn opt_dry_run() {
+        let opt = Opt::from_iter_safe(["croncat-indexer", "--dry-run", "backfill"]).unwrap();
+        assert_eq!(opt.command, Some(Command::Backfill));
+        assert!(opt.dry_run);
+    }
+
+    #[test]
+    fn opt_only() {
+        let opt = Opt::from_iter_safe(["croncat-indexer", "--only", "uni-5", "backfill"]).unwrap();
+        assert_eq!(opt.command, Some(Command::Backfill));
+        assert_eq!(opt.only.as_deref(), Some("uni-5"));
+
+        let opt = Opt::from_iter_safe(["croncat-indexer"]).unwrap();
+        assert_eq!(opt.only, None);
+    }
+
+    #[test]
+    fn opt_prune() {
+        let opt = Opt::from_iter_safe([
+            "croncat-indexer",
+            "prune",
+            "--chain-id",
+            "uni-5",
+            "--older-than",
+            "30",
+        ])
+        .unwrap();
+
+        assert_eq!(
+            opt.command,
+            Some(Command::Prune {
+                chain_id: "uni-5".to_string(),
+                older_than: 30,
+            })
+        );
+    }
+
+    #[test]
+    fn opt_gaps() {
+        let opt = Opt::from_iter_safe([
+            "croncat-indexer",
+            "gaps",
+            "--chain-id",
+            "uni-5",
+            "--days",
+            "30",
+        ])
+        .unwrap();
+        assert_eq!(
+            opt.command,
+            Some(Command::Gaps {
+                chain_id: "uni-5".to_string(),
+                days: 30,
+            })
+        );
+
+        let opt = Opt::from_iter_safe(["croncat-indexer", "gaps", "--chain-id", "uni-5"]).unwrap();
+        assert_eq!(
+            opt.command,
+            Some(Command::Gaps {
+                chain_id: "uni-5".to_string(),
+                days: 7,
+            })
+        );
+    }
+
+    #[test]
+    fn opt_verify() {
+        let opt = Opt::from_iter_safe([
+            "croncat-indexer",
+            "verify",
+            "--chain-id",
+            "uni-5",
+            "--from",
+            "100",
+            "--to",
+            "200",
+        ])
+        .unwrap();
+        assert_eq!(
+            opt.command,
+            Some(Command::Verify {
+                chain_id: "uni-5".to_string(),
+                from: 100,
+                to: 200,
+                rate: 10,
+            })
+        );
+
+        let opt = Opt::from_iter_safe([
+            "croncat-indexer",
+            "verify",
+            "--chain-id",
+            "uni-5",
+            "--from",
+            "100",
+            "--to",
+            "200",
+            "--rate",
+            "2",
+        ])
+        .unwrap();
+        assert!(matches!(opt.command, Some(Command::Verify { rate: 2, .. })));
+    }
+
+    #[test]
+    fn opt_show() {
+        let opt = Opt::from_iter_safe([
+            "croncat-indexer",
+            "show",
+            "--chain-id",
+            "uni-5",
+            "--height",
+            "1234",
+        ])
+        .unwrap();
+        assert_eq!(
+            opt.command,
+            Some(Command::Show {
+                chain_id: "uni-5".to_string(),
+                height: 1234,
+            })
+        );
+
+        assert!(Opt::from_iter_safe(["croncat-indexer", "show", "--chain-id", "uni-5"]).is_err());
+    }
+}
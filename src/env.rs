@@ -1,4 +1,88 @@
-use color_eyre::Result;
+use std::str::FromStr;
+
+use color_eyre::{eyre::eyre, Report, Result};
+use tracing::{info, Subscriber};
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::EnvFilter;
+
+/// The crate version with the git commit and rustc version it was built with, see `build.rs`.
+pub const LONG_VERSION: &str = concat!(
+    env!("CARGO_PKG_VERSION"),
+    " (commit ",
+    env!("CRONCAT_INDEXER_GIT_COMMIT"),
+    ", ",
+    env!("CRONCAT_INDEXER_RUSTC_VERSION"),
+    ")"
+);
+
+/// The log filter used when neither `RUST_LOG` nor `CRONCAT_INDEXER_LOG` is set.
+pub const DEFAULT_LOG_FILTER: &str = "none,croncat_indexer=info";
+
+/// The format logs are emitted in.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Human readable logs.
+    #[default]
+    Pretty,
+    /// One JSON object per line.
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = Report;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.to_lowercase().as_str() {
+            "pretty" | "text" => Ok(Self::Pretty),
+            "json" => Ok(Self::Json),
+            _ => Err(eyre!("Unknown log format: {}", value)),
+        }
+    }
+}
+
+impl LogFormat {
+    /// Read the log format from the `LOG_FORMAT` environment variable.
+    pub fn from_env() -> Result<Self> {
+        match std::env::var("LOG_FORMAT") {
+            Ok(value) => value.parse(),
+            Err(_) => Ok(Self::default()),
+        }
+    }
+
+    /// Build a subscriber emitting logs in this format to stderr, filtered by `RUST_LOG`.
+    ///
+    /// Stdout is left to the output of commands like `show` and `gaps`.
+    pub fn subscriber(&self) -> Box<dyn Subscriber + Send + Sync> {
+        let builder = tracing_subscriber::fmt()
+            .with_writer(std::io::stderr)
+            .with_env_filter(EnvFilter::from_default_env());
+
+        match self {
+            Self::Pretty => Box::new(builder.finish()),
+            Self::Json => Box::new(builder.json().finish()),
+        }
+    }
+}
+
+///
+/// Resolve the log filter, `RUST_LOG` wins over `CRONCAT_INDEXER_LOG` which wins over the default.
+///
+/// `CRONCAT_INDEXER_LOG` is either a level for the indexer (e.g. `debug`) or extra directives
+/// added on top of the default (e.g. `tendermint_rpc=debug`).
+///
+pub fn log_filter(rust_log: Option<String>, indexer_log: Option<String>) -> String {
+    if let Some(rust_log) = rust_log {
+        return rust_log;
+    }
+
+    match indexer_log {
+        Some(directives) if directives.contains('=') || directives.contains(',') => {
+            format!("{},{}", DEFAULT_LOG_FILTER, directives)
+        }
+        Some(level) => format!("none,croncat_indexer={}", level),
+        None => DEFAULT_LOG_FILTER.to_string(),
+    }
+}
 
 pub fn setup() -> Result<()> {
     if std::env::var("RUST_LIB_BACKTRACE").is_err() {
@@ -6,12 +90,56 @@ pub fn setup() -> Result<()> {
     }
     color_eyre::install()?;
 
-    if std::env::var("RUST_LOG").is_err() {
-        std::env::set_var("RUST_LOG", "none,croncat_indexer=info");
-    }
-    tracing_subscriber::fmt()
-        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
-        .init();
+    std::env::set_var(
+        "RUST_LOG",
+        log_filter(
+            std::env::var("RUST_LOG").ok(),
+            std::env::var("CRONCAT_INDEXER_LOG").ok(),
+        ),
+    );
+    LogFormat::from_env()?
+        .subscriber()
+        .try_init()
+        .map_err(|err| eyre!("Failed to initialize logging: {}", err))?;
+    info!("Starting croncat-indexer {}", LONG_VERSION);
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn log_format_from_str() {
+        assert_eq!("pretty".parse::<LogFormat>().unwrap(), LogFormat::Pretty);
+        assert_eq!("JSON".parse::<LogFormat>().unwrap(), LogFormat::Json);
+        assert!("xml".parse::<LogFormat>().is_err());
+    }
+
+    #[test]
+    fn log_filter_precedence() {
+        assert_eq!(
+            log_filter(Some("warn".to_string()), Some("debug".to_string())),
+            "warn"
+        );
+        assert_eq!(
+            log_filter(None, Some("debug".to_string())),
+            "none,croncat_indexer=debug"
+        );
+        assert_eq!(
+            log_filter(None, Some("tendermint_rpc=debug".to_string())),
+            "none,croncat_indexer=info,tendermint_rpc=debug"
+        );
+        assert_eq!(log_filter(None, None), DEFAULT_LOG_FILTER);
+    }
+
+    #[test]
+    fn log_format_subscriber() {
+        for format in [LogFormat::Pretty, LogFormat::Json] {
+            tracing::subscriber::with_default(format.subscriber(), || {
+                tracing::info!("Logging with {:?}", format);
+            });
+        }
+    }
+}
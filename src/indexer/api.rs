@@ -0,0 +1,399 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use axum::extract::{Extension, Query};
+use axum::http::StatusCode;
+use axum::routing::get;
+use axum::{Json, Router};
+use chrono::NaiveDateTime;
+use color_eyre::Result;
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, Order, PaginatorTrait, QueryFilter, QueryOrder,
+};
+use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info};
+
+use super::error::{IndexError, IndexResult};
+use super::model::block::Column as BlockColumn;
+use super::model::block::Entity as BlockEntity;
+use super::model::block::Model as DatabaseBlock;
+use super::model::transaction::Column as TransactionColumn;
+use super::model::transaction::Entity as TransactionEntity;
+use super::model::transaction::Model as DatabaseTransaction;
+use super::status::{self, StatusState};
+
+/// The default number of rows returned per page.
+pub const DEFAULT_PAGE_SIZE: u64 = 50;
+
+///
+/// The order rows are returned in, by height.
+///
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+impl From<SortOrder> for Order {
+    fn from(order: SortOrder) -> Self {
+        match order {
+            SortOrder::Asc => Order::Asc,
+            SortOrder::Desc => Order::Desc,
+        }
+    }
+}
+
+///
+/// Which page of the rows to return, pages start at 1.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Pagination {
+    #[serde(default = "default_page")]
+    pub page: u64,
+    #[serde(default = "default_page_size")]
+    pub per_page: u64,
+    #[serde(default)]
+    pub order: SortOrder,
+}
+
+fn default_page() -> u64 {
+    1
+}
+
+fn default_page_size() -> u64 {
+    DEFAULT_PAGE_SIZE
+}
+
+impl Default for Pagination {
+    fn default() -> Self {
+        Self {
+            page: default_page(),
+            per_page: default_page_size(),
+            order: SortOrder::default(),
+        }
+    }
+}
+
+impl Pagination {
+    ///
+    /// Check the page, and cap its size to `max_page_size`.
+    ///
+    fn validate(&self, max_page_size: u64) -> IndexResult<Self> {
+        if self.page == 0 {
+            return Err(IndexError::invalid_data("page must be at least 1"));
+        }
+        if self.per_page == 0 {
+            return Err(IndexError::invalid_data("per_page must be at least 1"));
+        }
+
+        Ok(Self {
+            per_page: self.per_page.min(max_page_size),
+            ..self.clone()
+        })
+    }
+}
+
+///
+/// A page of rows.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub page: u64,
+    pub per_page: u64,
+    pub total: u64,
+}
+
+///
+/// An indexed block.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiBlock {
+    pub id: String,
+    pub chain_id: String,
+    pub height: i64,
+    pub time: NaiveDateTime,
+    pub hash: String,
+    pub num_txs: i64,
+    pub proposer_address: Option<String>,
+    pub total_gas_used: i64,
+    pub total_gas_wanted: i64,
+}
+
+impl From<DatabaseBlock> for ApiBlock {
+    fn from(block: DatabaseBlock) -> Self {
+        Self {
+            id: block.id.to_string(),
+            chain_id: block.chain_id,
+            height: block.height,
+            time: block.time,
+            hash: block.hash,
+            num_txs: block.num_txs,
+            proposer_address: block.proposer_address,
+            total_gas_used: block.total_gas_used,
+            total_gas_wanted: block.total_gas_wanted,
+        }
+    }
+}
+
+///
+/// An indexed transaction.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiTransaction {
+    pub id: String,
+    pub chain_id: Option<String>,
+    pub height: i64,
+    pub hash: String,
+    pub code: i32,
+    pub gas_wanted: String,
+    pub gas_used: String,
+    pub events: Option<serde_json::Value>,
+    pub fee_amount: Option<String>,
+    pub fee_denom: Option<String>,
+    pub signer: Option<String>,
+}
+
+impl From<(DatabaseTransaction, Option<DatabaseBlock>)> for ApiTransaction {
+    fn from((transaction, block): (DatabaseTransaction, Option<DatabaseBlock>)) -> Self {
+        Self {
+            id: transaction.id.to_string(),
+            chain_id: block.map(|block| block.chain_id),
+            height: transaction.height,
+            hash: transaction.hash,
+            code: transaction.code,
+            gas_wanted: transaction.gas_wanted,
+            gas_used: transaction.gas_used,
+            events: transaction.events,
+            fee_amount: transaction.fee_amount,
+            fee_denom: transaction.fee_denom,
+            signer: transaction.signer,
+        }
+    }
+}
+
+///
+/// The blocks to list.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct BlocksQuery {
+    pub chain_id: String,
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+}
+
+///
+/// The transactions to list.
+///
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct TransactionsQuery {
+    pub hash: Option<String>,
+    pub chain_id: Option<String>,
+}
+
+///
+/// List the indexed blocks of a chain within an optional inclusive height range.
+///
+pub async fn get_blocks(
+    db: &DatabaseConnection,
+    query: &BlocksQuery,
+    pagination: &Pagination,
+    max_page_size: u64,
+) -> IndexResult<Page<ApiBlock>> {
+    let pagination = pagination.validate(max_page_size)?;
+    if let (Some(from), Some(to)) = (query.from, query.to) {
+        if from > to {
+            return Err(IndexError::invalid_data(format!(
+                "Invalid height range {} to {}",
+                from, to
+            )));
+        }
+    }
+
+    let mut select = BlockEntity::find().filter(BlockColumn::ChainId.eq(query.chain_id.as_str()));
+    if let Some(from) = query.from {
+        select = select.filter(BlockColumn::Height.gte(from));
+    }
+    if let Some(to) = query.to {
+        select = select.filter(BlockColumn::Height.lte(to));
+    }
+
+    let paginator = select
+        .order_by(BlockColumn::Height, pagination.order.into())
+        .paginate(db, pagination.per_page as usize);
+    let total = paginator.num_items().await? as u64;
+    let items = paginator.fetch_page(pagination.page as usize - 1).await?;
+
+    Ok(Page {
+        items: items.into_iter().map(ApiBlock::from).collect(),
+        page: pagination.page,
+        per_page: pagination.per_page,
+        total,
+    })
+}
+
+///
+/// List the indexed transactions, optionally with a hash or on a chain.
+///
+pub async fn get_transactions(
+    db: &DatabaseConnection,
+    query: &TransactionsQuery,
+    pagination: &Pagination,
+    max_page_size: u64,
+) -> IndexResult<Page<ApiTransaction>> {
+    let pagination = pagination.validate(max_page_size)?;
+
+    let mut select = TransactionEntity::find().find_also_related(BlockEntity);
+    if let Some(hash) = &query.hash {
+        // Hashes are stored upper case, as tendermint displays them.
+        select = select.filter(TransactionColumn::Hash.eq(hash.to_uppercase()));
+    }
+    if let Some(chain_id) = &query.chain_id {
+        select = select.filter(BlockColumn::ChainId.eq(chain_id.as_str()));
+    }
+
+    let order: Order = pagination.order.into();
+    let paginator = select
+        .order_by(TransactionColumn::Height, order.clone())
+        .order_by(TransactionColumn::Hash, order)
+        .paginate(db, pagination.per_page as usize);
+    let total = paginator.num_items().await? as u64;
+    let items = paginator.fetch_page(pagination.page as usize - 1).await?;
+
+    Ok(Page {
+        items: items.into_iter().map(ApiTransaction::from).collect(),
+        page: pagination.page,
+        per_page: pagination.per_page,
+        total,
+    })
+}
+
+///
+/// The state shared by the api handlers.
+///
+#[derive(Debug, Clone)]
+pub struct ApiState {
+    pub db: DatabaseConnection,
+    pub max_page_size: u64,
+}
+
+///
+/// Turn an error into a response, invalid queries are the caller's fault.
+///
+fn error_response(err: IndexError) -> (StatusCode, String) {
+    match err {
+        IndexError::InvalidData { message } => (StatusCode::BAD_REQUEST, message),
+        err => {
+            error!("Failed to query the indexed data: {}", err);
+            (StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
+        }
+    }
+}
+
+///
+/// Serve a page of blocks as JSON.
+///
+async fn blocks(
+    Extension(state): Extension<Arc<ApiState>>,
+    Query(query): Query<BlocksQuery>,
+    Query(pagination): Query<Pagination>,
+) -> Result<Json<Page<ApiBlock>>, (StatusCode, String)> {
+    get_blocks(&state.db, &query, &pagination, state.max_page_size)
+        .await
+        .map(Json)
+        .map_err(error_response)
+}
+
+///
+/// Serve a page of transactions as JSON.
+///
+async fn transactions(
+    Extension(state): Extension<Arc<ApiState>>,
+    Query(query): Query<TransactionsQuery>,
+    Query(pagination): Query<Pagination>,
+) -> Result<Json<Page<ApiTransaction>>, (StatusCode, String)> {
+    get_transactions(&state.db, &query, &pagination, state.max_page_size)
+        .await
+        .map(Json)
+        .map_err(error_response)
+}
+
+///
+/// Build the api routes.
+///
+pub fn router(state: ApiState) -> Router {
+    Router::new()
+        .route("/blocks", get(blocks))
+        .route("/transactions", get(transactions))
+        .layer(Extension(Arc::new(state)))
+}
+
+///
+/// Serve the api along with the status routes on an address until the shutdown token is cancelled.
+///
+pub async fn serve(
+    addr: SocketAddr,
+    state: ApiState,
+    status_state: StatusState,
+    shutdown: CancellationToken,
+) -> Result<()> {
+    info!(
+        "Serving the query api on http://{}, the indexing status on /status and metrics on /metrics",
+        addr
+    );
+
+    axum::Server::try_bind(&addr)?
+        .serve(
+            router(state)
+                .merge(status::router(status_state))
+                .into_make_service(),
+        )
+        .with_graceful_shutdown(async move { shutdown.cancelled().await })
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pagination_deserialize() {
+        let pagination: Pagination = serde_json::from_value(serde_json::json!({})).unwrap();
+        assert_eq!(pagination, Pagination::default());
+
+        let pagination: Pagination = serde_json::from_value(serde_json::json!({
+            "page": 3,
+            "per_page": 10,
+            "order": "desc",
+        }))
+        .unwrap();
+        assert_eq!(
+            pagination,
+            Pagination {
+                page: 3,
+                per_page: 10,
+                order: SortOrder::Desc,
+            }
+        );
+    }
+
+    #[test]
+    fn pagination_validate() {
+        let pagination = Pagination {
+            per_page: 1000,
+            ..Pagination::default()
+        };
+        assert_eq!(pagination.validate(100).unwrap().per_page, 100);
+
+        let pagination = Pagination {
+            page: 0,
+            ..Pagination::default()
+        };
+        assert!(pagination.validate(100).is_err());
+    }
+}
@@ -0,0 +1,219 @@
+use chrono::Utc;
+use enum_display::EnumDisplay;
+use sea_orm::prelude::Uuid;
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryFilter,
+    QueryOrder, Set,
+};
+
+use super::error::IndexResult;
+use super::historical::BlockRange;
+use super::model::backfill_job::ActiveModel as BackfillJobModel;
+use super::model::backfill_job::Column as BackfillJobColumn;
+use super::model::backfill_job::Entity as BackfillJobEntity;
+use super::model::backfill_job::Model as BackfillJob;
+
+///
+/// The state of a backfill job.
+///
+#[derive(Debug, Copy, Clone, EnumDisplay, PartialEq, Eq)]
+#[enum_display(case = "Kebab")]
+pub enum BackfillStatus {
+    /// Waiting to be indexed.
+    Pending,
+    /// Being indexed, or interrupted by a crash.
+    Running,
+    /// The last attempt failed, it is retried on the next historical pass.
+    Failed,
+    /// No longer needed, its heights were indexed or are covered by another job.
+    Done,
+}
+
+impl BackfillJob {
+    ///
+    /// The heights of the job.
+    ///
+    pub fn range(&self) -> BlockRange {
+        (self.start_height, self.end_height).into()
+    }
+
+    ///
+    /// Whether the job shares any height with a range.
+    ///
+    pub fn overlaps(&self, range: &BlockRange) -> bool {
+        let (start, end) = **range;
+        self.start_height <= end && start <= self.end_height
+    }
+}
+
+///
+/// Record the ranges of the current gaps of a chain as backfill jobs.
+///
+/// Each range takes over the first unfinished job it overlaps, keeping its attempts and last error
+/// as the gap shrinks, or gets a new pending job. The unfinished jobs left over are done.
+///
+pub async fn sync_backfill_jobs(
+    db: &DatabaseConnection,
+    chain_id: &str,
+    ranges: &[BlockRange],
+) -> IndexResult<()> {
+    let now = Utc::now().naive_utc();
+    let mut unfinished = get_pending_backfill_jobs(db, chain_id).await?;
+    unfinished.sort_by_key(|job| job.start_height);
+
+    for range in ranges {
+        let (start, end) = **range;
+        match unfinished.iter().position(|job| job.overlaps(range)) {
+            Some(index) => {
+                let job = unfinished.remove(index);
+                if job.start_height != start || job.end_height != end {
+                    let mut job = job.into_active_model();
+                    job.start_height = Set(start);
+                    job.end_height = Set(end);
+                    job.updated_at = Set(now);
+                    job.update(db).await?;
+                }
+            }
+            None => {
+                BackfillJobModel {
+                    id: Set(Uuid::new_v4()),
+                    chain_id: Set(chain_id.to_string()),
+                    start_height: Set(start),
+                    end_height: Set(end),
+                    status: Set(BackfillStatus::Pending.to_string()),
+                    attempts: Set(0),
+                    last_error: Set(None),
+                    created_at: Set(now),
+                    updated_at: Set(now),
+                }
+                .insert(db)
+                .await?;
+            }
+        }
+    }
+
+    if !unfinished.is_empty() {
+        BackfillJobEntity::update_many()
+            .col_expr(
+                BackfillJobColumn::Status,
+                Expr::value(BackfillStatus::Done.to_string()),
+            )
+            .col_expr(BackfillJobColumn::UpdatedAt, Expr::value(now))
+            .filter(BackfillJobColumn::Id.is_in(unfinished.into_iter().map(|job| job.id)))
+            .exec(db)
+            .await?;
+    }
+
+    Ok(())
+}
+
+///
+/// List the unfinished backfill jobs of a chain, the least attempted and most recent heights first.
+///
+pub async fn get_pending_backfill_jobs(
+    db: &DatabaseConnection,
+    chain_id: &str,
+) -> IndexResult<Vec<BackfillJob>> {
+    Ok(BackfillJobEntity::find()
+        .filter(BackfillJobColumn::ChainId.eq(chain_id))
+        .filter(BackfillJobColumn::Status.ne(BackfillStatus::Done.to_string()))
+        .order_by_asc(BackfillJobColumn::Attempts)
+        .order_by_desc(BackfillJobColumn::StartHeight)
+        .all(db)
+        .await?)
+}
+
+///
+/// Update the status of a job, and its last error when given.
+///
+async fn set_backfill_status(
+    db: &DatabaseConnection,
+    job: BackfillJob,
+    status: BackfillStatus,
+    last_error: Option<String>,
+) -> IndexResult<BackfillJob> {
+    let attempts = job.attempts;
+    let mut job = job.into_active_model();
+    job.status = Set(status.to_string());
+    if status == BackfillStatus::Running {
+        job.attempts = Set(attempts + 1);
+    }
+    if last_error.is_some() {
+        job.last_error = Set(last_error);
+    }
+    job.updated_at = Set(Utc::now().naive_utc());
+
+    Ok(job.update(db).await?)
+}
+
+///
+/// Mark a job as running, counting the attempt.
+///
+pub async fn start_backfill_job(
+    db: &DatabaseConnection,
+    job: BackfillJob,
+) -> IndexResult<BackfillJob> {
+    set_backfill_status(db, job, BackfillStatus::Running, None).await
+}
+
+///
+/// Mark a job as done once its heights are indexed.
+///
+pub async fn finish_backfill_job(
+    db: &DatabaseConnection,
+    job: BackfillJob,
+) -> IndexResult<BackfillJob> {
+    set_backfill_status(db, job, BackfillStatus::Done, None).await
+}
+
+///
+/// Put an interrupted job back in the queue.
+///
+pub async fn release_backfill_job(
+    db: &DatabaseConnection,
+    job: BackfillJob,
+) -> IndexResult<BackfillJob> {
+    set_backfill_status(db, job, BackfillStatus::Pending, None).await
+}
+
+///
+/// Mark a job as failed with the error of its last attempt.
+///
+pub async fn fail_backfill_job(
+    db: &DatabaseConnection,
+    job: BackfillJob,
+    error: String,
+) -> IndexResult<BackfillJob> {
+    set_backfill_status(db, job, BackfillStatus::Failed, Some(error)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDateTime;
+
+    use super::*;
+
+    #[test]
+    fn backfill_job_overlaps() {
+        let now = NaiveDateTime::from_timestamp(0, 0);
+        let job = BackfillJob {
+            id: Uuid::nil(),
+            chain_id: "uni-5".to_string(),
+            start_height: 10,
+            end_height: 20,
+            status: BackfillStatus::Pending.to_string(),
+            attempts: 0,
+            last_error: None,
+            created_at: now,
+            updated_at: now,
+        };
+
+        assert_eq!(job.status, "pending");
+        assert!(job.overlaps(&(15, 30).into()));
+        assert!(job.overlaps(&(20, 20).into()));
+        assert!(job.overlaps(&(1, 10).into()));
+        assert!(!job.overlaps(&(21, 30).into()));
+        assert!(!job.overlaps(&(1, 9).into()));
+    }
+}
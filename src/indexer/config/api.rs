@@ -0,0 +1,147 @@
+use std::net::SocketAddr;
+
+use color_eyre::{eyre::eyre, Result};
+use serde::{Deserialize, Serialize};
+
+use super::database::parse_var;
+use super::Config;
+use crate::indexer::error::{IndexError, IndexResult};
+
+/// The default maximum number of rows returned per page by the query api.
+pub const DEFAULT_API_MAX_PAGE_SIZE: u64 = 100;
+
+fn default_api_max_page_size() -> u64 {
+    DEFAULT_API_MAX_PAGE_SIZE
+}
+
+/// Api server settings, read from the `api` section of the configurations or the environment.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiConfig {
+    /// The address to serve the query api and the indexing status on, disabled when unset (`CRONCAT_INDEXER_API_ADDRESS`).
+    #[serde(default)]
+    pub address: Option<SocketAddr>,
+    /// The maximum number of rows returned per page (`CRONCAT_INDEXER_API_MAX_PAGE_SIZE`).
+    #[serde(alias = "max-page-size", default = "default_api_max_page_size")]
+    pub max_page_size: u64,
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self {
+            address: None,
+            max_page_size: DEFAULT_API_MAX_PAGE_SIZE,
+        }
+    }
+}
+
+impl ApiConfig {
+    /// Validate values that cannot be expressed by the types alone.
+    pub fn validate(&self) -> IndexResult<()> {
+        if self.max_page_size == 0 {
+            return Err(IndexError::config("api max_page_size must be at least 1"));
+        }
+
+        Ok(())
+    }
+
+    /// Read the api configuration from the `api` section of the configurations, which must agree
+    /// when several have one, falling back to the environment when none has.
+    pub fn from_configs<'a>(configs: impl IntoIterator<Item = &'a Config>) -> Result<Self> {
+        let mut api_configs = configs.into_iter().filter_map(|config| config.api.as_ref());
+        let api_config = match api_configs.next() {
+            Some(api_config) => api_config,
+            None => return Self::from_env(),
+        };
+        if let Some(other) = api_configs.find(|other| *other != api_config) {
+            return Err(eyre!(
+                "Conflicting api configurations {:?} and {:?}, set it in a single configuration",
+                api_config,
+                other
+            ));
+        }
+
+        Ok(api_config.clone())
+    }
+
+    /// Read the api configuration from the environment.
+    pub fn from_env() -> Result<Self> {
+        Self::from_vars(|key| std::env::var(key).ok())
+    }
+
+    /// Read the api configuration from a variable lookup, falling back to the defaults.
+    pub fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let default = Self::default();
+
+        let config = Self {
+            address: parse_var(&var, "CRONCAT_INDEXER_API_ADDRESS")?,
+            max_page_size: parse_var(&var, "CRONCAT_INDEXER_API_MAX_PAGE_SIZE")?
+                .unwrap_or(default.max_page_size),
+        };
+        if config.max_page_size == 0 {
+            return Err(eyre!(
+                "CRONCAT_INDEXER_API_MAX_PAGE_SIZE must be at least 1"
+            ));
+        }
+
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn from_vars(vars: &[(&str, &str)]) -> Result<ApiConfig> {
+        let vars = vars
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect::<HashMap<_, _>>();
+        ApiConfig::from_vars(|key| vars.get(key).cloned())
+    }
+
+    #[test]
+    fn api_config_from_vars() {
+        assert_eq!(from_vars(&[]).unwrap(), ApiConfig::default());
+
+        let config = from_vars(&[
+            ("CRONCAT_INDEXER_API_ADDRESS", "0.0.0.0:8081"),
+            ("CRONCAT_INDEXER_API_MAX_PAGE_SIZE", "500"),
+        ])
+        .unwrap();
+        assert_eq!(config.address, Some("0.0.0.0:8081".parse().unwrap()));
+        assert_eq!(config.max_page_size, 500);
+
+        assert!(from_vars(&[("CRONCAT_INDEXER_API_ADDRESS", "localhost")]).is_err());
+        assert!(from_vars(&[("CRONCAT_INDEXER_API_MAX_PAGE_SIZE", "0")]).is_err());
+    }
+
+    #[test]
+    fn api_config_from_configs() {
+        let config = |api: &str| -> Config {
+            serde_yaml::from_str(&format!(
+                "name: test\nchain_id: uni-5\nsources: []\nfilters: []\n{}",
+                api
+            ))
+            .unwrap()
+        };
+        let without_api = config("");
+        let with_api = config("api:\n  address: 0.0.0.0:8081\n  max-page-size: 500\n");
+        let other_api = config("api:\n  address: 0.0.0.0:8082\n");
+
+        let api_config = ApiConfig::from_configs([&without_api, &with_api, &with_api]).unwrap();
+        assert_eq!(api_config.address, Some("0.0.0.0:8081".parse().unwrap()));
+        assert_eq!(api_config.max_page_size, 500);
+
+        assert_eq!(
+            other_api.api.as_ref().unwrap().max_page_size,
+            DEFAULT_API_MAX_PAGE_SIZE
+        );
+        assert!(ApiConfig::from_configs([&with_api, &other_api]).is_err());
+
+        let mut invalid = with_api.clone();
+        invalid.api.as_mut().unwrap().max_page_size = 0;
+        assert!(invalid.validate().is_err());
+    }
+}
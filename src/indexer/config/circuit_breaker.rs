@@ -0,0 +1,78 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// The default number of consecutive failures of a source before it is skipped.
+pub const DEFAULT_FAILURE_THRESHOLD: u32 = 5;
+
+/// The default number of seconds a failing source is skipped for.
+pub const DEFAULT_COOLDOWN_SECS: u64 = 30;
+
+fn default_failure_threshold() -> u32 {
+    DEFAULT_FAILURE_THRESHOLD
+}
+
+fn default_cooldown_secs() -> u64 {
+    DEFAULT_COOLDOWN_SECS
+}
+
+/// Circuit breaker configuration for the rpc sources of an indexer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CircuitBreakerConfig {
+    /// The number of consecutive failures after which a source is skipped, `0` never skips it.
+    #[serde(alias = "failure-threshold", default = "default_failure_threshold")]
+    pub failure_threshold: u32,
+    /// The number of seconds a source is skipped for before a single request tests its recovery.
+    #[serde(alias = "cooldown-secs", default = "default_cooldown_secs")]
+    pub cooldown_secs: u64,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self::new(DEFAULT_FAILURE_THRESHOLD, DEFAULT_COOLDOWN_SECS)
+    }
+}
+
+impl CircuitBreakerConfig {
+    /// Create a new circuit breaker configuration.
+    pub fn new(failure_threshold: u32, cooldown_secs: u64) -> Self {
+        Self {
+            failure_threshold,
+            cooldown_secs,
+        }
+    }
+
+    /// Whether the breaker ever skips a source.
+    pub fn is_enabled(&self) -> bool {
+        self.failure_threshold > 0
+    }
+
+    /// How long a source is skipped for once the breaker opens.
+    pub fn cooldown(&self) -> Duration {
+        Duration::from_secs(self.cooldown_secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn circuit_breaker_config_deserialize() {
+        let yaml = indoc::indoc! {r#"
+            failure-threshold: 3
+            cooldown-secs: 120
+        "#};
+
+        let breaker: CircuitBreakerConfig = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(breaker, CircuitBreakerConfig::new(3, 120));
+        assert_eq!(breaker.cooldown(), Duration::from_secs(120));
+
+        let breaker: CircuitBreakerConfig = serde_yaml::from_str("cooldown-secs: 10").unwrap();
+        assert_eq!(
+            breaker,
+            CircuitBreakerConfig::new(DEFAULT_FAILURE_THRESHOLD, 10)
+        );
+        assert!(!CircuitBreakerConfig::new(0, 10).is_enabled());
+    }
+}
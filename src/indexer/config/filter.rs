@@ -1,11 +1,18 @@
+use std::fmt;
 use std::ops::Deref;
+use std::str::FromStr;
 
-use color_eyre::Report;
-use regex::Regex;
+use cosmos_sdk_proto::cosmos::tx::v1beta1::Tx;
+use enum_display::EnumDisplay;
+use prost::Message;
+use regex::{Regex, RegexSet};
 use serde::{Deserialize, Serialize};
 use tendermint::abci;
 use tendermint_rpc::endpoint::tx;
 
+use super::Config;
+use crate::indexer::error::{IndexError, IndexResult};
+
 #[derive(Debug, Clone)]
 /// Filter a field by a regex.
 pub struct FilterPattern(Regex);
@@ -19,7 +26,7 @@ impl Deref for FilterPattern {
 }
 
 impl TryFrom<&str> for FilterPattern {
-    type Error = Report;
+    type Error = IndexError;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
         Ok(Self(Regex::new(value)?))
@@ -54,52 +61,424 @@ impl<'de> Deserialize<'de> for FilterPattern {
     }
 }
 
+/// The value of an attribute to filter by, a single pattern or a list of alternatives.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ValueFilter {
+    Pattern(FilterPattern),
+    AnyOf(Vec<FilterPattern>),
+}
+
+impl ValueFilter {
+    /// The patterns to try, an empty list never matches.
+    pub fn patterns(&self) -> &[FilterPattern] {
+        match self {
+            Self::Pattern(pattern) => std::slice::from_ref(pattern),
+            Self::AnyOf(patterns) => patterns,
+        }
+    }
+
+    /// Whether any of the patterns matches a value.
+    pub fn is_match(&self, value: &str) -> bool {
+        self.patterns()
+            .iter()
+            .any(|pattern| pattern.is_match(value))
+    }
+}
+
+impl From<FilterPattern> for ValueFilter {
+    fn from(pattern: FilterPattern) -> Self {
+        Self::Pattern(pattern)
+    }
+}
+
+impl TryFrom<&str> for ValueFilter {
+    type Error = IndexError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Ok(FilterPattern::try_from(value)?.into())
+    }
+}
+
 /// Attributes to filter by.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct AttributeFilter {
     pub key: FilterPattern,
-    pub value: Option<FilterPattern>,
+    /// Match when any of the patterns matches the value.
+    pub value: Option<ValueFilter>,
+    /// Require that no attribute of the event matches instead.
+    #[serde(default)]
+    pub negate: bool,
+}
+
+impl AttributeFilter {
+    /// Whether the attribute key matches, and its value too when one is given (ignores `negate`).
+    pub fn matches(&self, attribute: &abci::tag::Tag) -> bool {
+        self.matches_str(&attribute.key.to_string(), &attribute.value.to_string())
+    }
+
+    /// Whether an attribute's key and value match, see [`AttributeFilter::matches`].
+    pub fn matches_str(&self, key: &str, value: &str) -> bool {
+        self.key.is_match(key)
+            && self
+                .value
+                .as_ref()
+                .map_or(true, |filter| filter.is_match(value))
+    }
+}
+
+/// An event with its attributes converted to strings once, to match many filters against.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreparedEvent<'a> {
+    pub type_str: &'a str,
+    pub attributes: Vec<(String, String)>,
+}
+
+impl<'a> From<&'a abci::Event> for PreparedEvent<'a> {
+    fn from(event: &'a abci::Event) -> Self {
+        Self {
+            type_str: event.type_str.as_str(),
+            attributes: event
+                .attributes
+                .iter()
+                .map(|attribute| (attribute.key.to_string(), attribute.value.to_string()))
+                .collect(),
+        }
+    }
+}
+
+/// Match the result code of a transaction, `0` for success or `!0` for any failure.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CodeFilter {
+    pub code: u32,
+    /// Match every other code instead.
+    pub negate: bool,
+}
+
+impl CodeFilter {
+    /// Whether a transaction's result code matches.
+    pub fn matches(&self, code: abci::Code) -> bool {
+        (code.value() == self.code) != self.negate
+    }
+}
+
+impl FromStr for CodeFilter {
+    type Err = IndexError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (negate, code) = match value.trim().strip_prefix('!') {
+            Some(code) => (true, code),
+            None => (false, value.trim()),
+        };
+        let code = code
+            .trim()
+            .parse()
+            .map_err(|_| IndexError::config(format!("Invalid code filter {:?}", value)))?;
+
+        Ok(Self { code, negate })
+    }
+}
+
+impl fmt::Display for CodeFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.negate {
+            write!(f, "!")?;
+        }
+        write!(f, "{}", self.code)
+    }
+}
+
+impl Serialize for CodeFilter {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        if self.negate {
+            serializer.serialize_str(&self.to_string())
+        } else {
+            serializer.serialize_u32(self.code)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for CodeFilter {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum RawCodeFilter {
+            Code(u32),
+            Pattern(String),
+        }
+
+        match RawCodeFilter::deserialize(deserializer)? {
+            RawCodeFilter::Code(code) => Ok(Self {
+                code,
+                negate: false,
+            }),
+            RawCodeFilter::Pattern(pattern) => pattern.parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+/// What a filter matches against.
+#[derive(Debug, Copy, Clone, Default, EnumDisplay, PartialEq, Eq, Serialize, Deserialize)]
+#[enum_display(case = "Kebab")]
+#[serde(rename_all = "kebab-case")]
+pub enum FilterKind {
+    /// Match abci events by their type and attributes.
+    #[default]
+    Event,
+    /// Match the type url of any message in the decoded transaction (e.g. `/cosmwasm.wasm.v1.MsgExecuteContract`).
+    ///
+    /// NOTE: This decodes the full transaction for every filter and transaction, which is considerably
+    /// slower than matching events, so prefer event filters when they can express the same thing.
+    Message,
 }
 
 /// A filter is a set of rules that determine which data is indexed.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Filter {
+    /// A name to refer to the filter by, e.g. from a webhook.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub kind: FilterKind,
     #[serde(alias = "type", rename = "type")]
     pub type_str: FilterPattern,
+    /// Keep transactions that don't match the filter instead.
+    #[serde(default)]
+    pub negate: bool,
+    /// Only match transactions with this result code, checked before (and regardless of `negate`)
+    /// the events or messages.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<CodeFilter>,
+    /// Attributes of the matching event, unused by message filters.
+    #[serde(default)]
     pub attributes: Vec<AttributeFilter>,
 }
 
 impl Filter {
     pub fn matches(&self, response: &tx::Response) -> bool {
-        *self == response.tx_result.events
+        if let Some(code) = &self.code {
+            if !code.matches(response.tx_result.code) {
+                return false;
+            }
+        }
+
+        match self.kind {
+            FilterKind::Event => *self == response.tx_result.events,
+            FilterKind::Message => {
+                self.matches_message_types(response.tx.as_bytes()) != self.negate
+            }
+        }
+    }
+
+    /// Whether an event's type matches, and every attribute filter matches at least one of its
+    /// attributes (or none of them when negated), ignoring the filter's own `negate`.
+    pub fn matches_event(&self, event: &abci::Event) -> bool {
+        self.matches_prepared_event(&event.into())
     }
+
+    /// Whether a prepared event matches, see [`Filter::matches_event`].
+    pub fn matches_prepared_event(&self, event: &PreparedEvent) -> bool {
+        self.type_str.is_match(event.type_str) && self.matches_attributes(event)
+    }
+
+    /// Whether every attribute filter matches at least one attribute of an event (or none of them
+    /// when negated), regardless of its type.
+    pub fn matches_attributes(&self, event: &PreparedEvent) -> bool {
+        self.attributes.iter().all(|filter| {
+            let found = event
+                .attributes
+                .iter()
+                .any(|(key, value)| filter.matches_str(key, value));
+            found != filter.negate
+        })
+    }
+
+    /// Whether any message in the raw transaction has a type url matching this filter (ignores `negate`).
+    pub fn matches_message_types(&self, tx: &[u8]) -> bool {
+        message_type_urls(tx)
+            .iter()
+            .any(|type_url| self.type_str.is_match(type_url))
+    }
+}
+
+/// Decode the type urls of the messages in a raw `cosmos.tx.v1beta1.Tx`, empty if it can't be decoded.
+pub fn message_type_urls(tx: &[u8]) -> Vec<String> {
+    Tx::decode(tx)
+        .ok()
+        .and_then(|tx| tx.body)
+        .map(|body| {
+            body.messages
+                .into_iter()
+                .map(|message| message.type_url)
+                .collect()
+        })
+        .unwrap_or_default()
 }
 
 impl PartialEq<Vec<abci::Event>> for Filter {
+    ///
+    /// A filter matches when some event's type matches, and every attribute filter
+    /// matches at least one attribute of that same event (or none of them when negated).
+    ///
     fn eq(&self, other: &Vec<abci::Event>) -> bool {
-        let mut matches = false;
-        for event in other {
-            if self.type_str.is_match(event.type_str.as_str()) {
-                for attribute in &event.attributes {
-                    for filter in &self.attributes {
-                        if filter.key.is_match(attribute.key.to_string().as_str()) {
-                            if let Some(value) = &filter.value {
-                                if value.is_match(attribute.value.to_string().as_str()) {
-                                    matches = true;
-                                }
-                            }
-                        }
-                    }
+        let matches = other.iter().any(|event| self.matches_event(event));
+        matches != self.negate
+    }
+}
+
+/// How the top-level list of filters combine.
+#[derive(Debug, Copy, Clone, Default, EnumDisplay, PartialEq, Eq, Serialize, Deserialize)]
+#[enum_display(case = "Kebab")]
+#[serde(rename_all = "kebab-case")]
+pub enum FilterCombinator {
+    /// Keep a transaction if it matches every filter.
+    #[default]
+    All,
+    /// Keep a transaction if it matches at least one filter.
+    Any,
+}
+
+impl FilterCombinator {
+    /// Whether a transaction passes the given filters, an empty list of filters keeps everything.
+    pub fn matches(&self, filters: &[Filter], response: &tx::Response) -> bool {
+        self.combine(filters, |filter| filter.matches(response))
+    }
+
+    /// Combine the result of a predicate over the given filters.
+    pub fn combine(&self, filters: &[Filter], predicate: impl Fn(&Filter) -> bool) -> bool {
+        if filters.is_empty() {
+            return true;
+        }
+
+        match self {
+            Self::All => filters.iter().all(predicate),
+            Self::Any => filters.iter().any(predicate),
+        }
+    }
+}
+
+///
+/// The filters of a configuration and how they combine, built once per run to match every
+/// transaction against.
+///
+/// The events of a transaction are converted to strings once (and its messages decoded once),
+/// and their types are tested against the type patterns of every filter in a single pass of a
+/// [`RegexSet`]. Only the attributes of events whose type matched a filter are checked.
+///
+#[derive(Debug, Clone)]
+pub struct CompiledFilterSet {
+    filters: Vec<Filter>,
+    combinator: FilterCombinator,
+    types: RegexSet,
+    has_message_filters: bool,
+}
+
+impl CompiledFilterSet {
+    /// Prepare a list of filters combined with `combinator`.
+    pub fn new(filters: &[Filter], combinator: FilterCombinator) -> IndexResult<Self> {
+        Ok(Self {
+            filters: filters.to_vec(),
+            combinator,
+            types: RegexSet::new(filters.iter().map(|filter| filter.type_str.as_str()))?,
+            has_message_filters: filters
+                .iter()
+                .any(|filter| filter.kind == FilterKind::Message),
+        })
+    }
+
+    /// Prepare the filters of a configuration.
+    pub fn from_config(config: &Config) -> IndexResult<Self> {
+        Self::new(&config.filters, config.filter_combinator)
+    }
+
+    /// Whether a transaction passes the filters, an empty set of filters keeps everything.
+    pub fn matches(&self, response: &tx::Response) -> bool {
+        if self.filters.is_empty() {
+            return true;
+        }
+
+        let events = response
+            .tx_result
+            .events
+            .iter()
+            .map(PreparedEvent::from)
+            .collect::<Vec<_>>();
+        let event_types = events
+            .iter()
+            .map(|event| self.types.matches(event.type_str))
+            .collect::<Vec<_>>();
+        let message_types = if self.has_message_filters {
+            message_type_urls(response.tx.as_bytes())
+                .iter()
+                .map(|type_url| self.types.matches(type_url))
+                .collect()
+        } else {
+            vec![]
+        };
+
+        let filter_matches = |(index, filter): (usize, &Filter)| {
+            if let Some(code) = &filter.code {
+                if !code.matches(response.tx_result.code) {
+                    return false;
                 }
             }
+
+            let matches = match filter.kind {
+                FilterKind::Event => events
+                    .iter()
+                    .zip(&event_types)
+                    .any(|(event, types)| types.matched(index) && filter.matches_attributes(event)),
+                FilterKind::Message => message_types.iter().any(|types| types.matched(index)),
+            };
+            matches != filter.negate
+        };
+
+        let mut filters = self.filters.iter().enumerate();
+        match self.combinator {
+            FilterCombinator::All => filters.all(filter_matches),
+            FilterCombinator::Any => filters.any(filter_matches),
         }
-        matches
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::indexer::tests::transaction_response;
+
+    fn event(type_str: &str, attributes: &[(&str, &str)]) -> abci::Event {
+        abci::Event {
+            type_str: type_str.to_string(),
+            attributes: attributes
+                .iter()
+                .map(|(key, value)| abci::tag::Tag {
+                    key: key.parse().unwrap(),
+                    value: value.parse().unwrap(),
+                })
+                .collect(),
+        }
+    }
+
+    fn filter(type_str: &str, key: &str, value: &str) -> Filter {
+        Filter {
+            name: None,
+            kind: FilterKind::Event,
+            type_str: type_str.try_into().unwrap(),
+            negate: false,
+            code: None,
+            attributes: vec![AttributeFilter {
+                key: key.try_into().unwrap(),
+                value: Some(value.try_into().unwrap()),
+                negate: false,
+            }],
+        }
+    }
 
     #[test]
     fn filter_pattern_try_from() {
@@ -134,7 +513,8 @@ mod tests {
     fn attribute_filter_serialize() {
         let attribute_filter = AttributeFilter {
             key: FilterPattern::try_from(".*").unwrap(),
-            value: Some(FilterPattern::try_from(".*").unwrap()),
+            value: Some(FilterPattern::try_from(".*").unwrap().into()),
+            negate: false,
         };
         let yaml = serde_yaml::to_string(&attribute_filter).unwrap();
         assert_eq!(
@@ -142,6 +522,7 @@ mod tests {
             indoc::indoc! {r#"
                 key: .*
                 value: .*
+                negate: false
             "#}
         );
     }
@@ -156,18 +537,97 @@ mod tests {
         let attribute_filter: AttributeFilter = serde_yaml::from_str(yaml).unwrap();
 
         assert_eq!(attribute_filter.key.as_str(), ".*");
-        assert_eq!(attribute_filter.value.unwrap().as_str(), ".*");
+        assert_eq!(
+            attribute_filter.value,
+            Some(FilterPattern::try_from(".*").unwrap().into())
+        );
+    }
+
+    #[test]
+    fn attribute_filter_value_list() {
+        let yaml = indoc::indoc! {r#"
+            key: method
+            value:
+            - ^proxy_call$
+            - ^create_task$
+        "#};
+
+        let attribute_filter: AttributeFilter = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(
+            attribute_filter.value,
+            Some(ValueFilter::AnyOf(vec![
+                FilterPattern::try_from("^proxy_call$").unwrap(),
+                FilterPattern::try_from("^create_task$").unwrap(),
+            ]))
+        );
+
+        // Both forms serialize back the way they are written.
+        assert_eq!(
+            serde_yaml::to_string(&attribute_filter).unwrap(),
+            indoc::indoc! {r#"
+                key: method
+                value:
+                - ^proxy_call$
+                - ^create_task$
+                negate: false
+            "#}
+        );
+        let attribute_filter = AttributeFilter {
+            value: Some("^proxy_call$".try_into().unwrap()),
+            ..attribute_filter
+        };
+        assert_eq!(
+            serde_yaml::to_string(&attribute_filter).unwrap(),
+            indoc::indoc! {r#"
+                key: method
+                value: ^proxy_call$
+                negate: false
+            "#}
+        );
+
+        // Invalid patterns are rejected in lists too.
+        let yaml = indoc::indoc! {r#"
+            key: method
+            value:
+            - ^proxy_call$
+            - "*."
+        "#};
+        assert!(serde_yaml::from_str::<AttributeFilter>(yaml).is_err());
+    }
+
+    #[test]
+    fn attribute_filter_matches_any_value() {
+        let attribute_filter = AttributeFilter {
+            key: "^method$".try_into().unwrap(),
+            value: Some(ValueFilter::AnyOf(vec![
+                "^proxy_call$".try_into().unwrap(),
+                "^create_task$".try_into().unwrap(),
+            ])),
+            negate: false,
+        };
+        assert!(attribute_filter.matches_str("method", "proxy_call"));
+        assert!(attribute_filter.matches_str("method", "create_task"));
+        assert!(!attribute_filter.matches_str("method", "remove_task"));
+        assert!(!attribute_filter.matches_str("action", "proxy_call"));
+
+        let attribute_filter = AttributeFilter {
+            value: Some(ValueFilter::AnyOf(vec![])),
+            ..attribute_filter
+        };
+        assert!(!attribute_filter.matches_str("method", "proxy_call"));
     }
 
     #[test]
     fn attribute_filter_eq() {
         let attribute_filter1 = AttributeFilter {
             key: FilterPattern::try_from(".*").unwrap(),
-            value: Some(FilterPattern::try_from(".*").unwrap()),
+            value: Some(FilterPattern::try_from(".*").unwrap().into()),
+            negate: false,
         };
         let attribute_filter2 = AttributeFilter {
             key: FilterPattern::try_from(".*").unwrap(),
-            value: Some(FilterPattern::try_from(".*").unwrap()),
+            value: Some(FilterPattern::try_from(".*").unwrap().into()),
+            negate: false,
         };
         assert_eq!(attribute_filter1, attribute_filter2);
     }
@@ -175,20 +635,28 @@ mod tests {
     #[test]
     fn filter_serialize() {
         let filter = Filter {
+            name: None,
+            kind: FilterKind::Event,
             type_str: FilterPattern::try_from(".*").unwrap(),
+            negate: false,
+            code: None,
             attributes: vec![AttributeFilter {
                 key: FilterPattern::try_from(".*").unwrap(),
-                value: Some(FilterPattern::try_from(".*").unwrap()),
+                value: Some(FilterPattern::try_from(".*").unwrap().into()),
+                negate: false,
             }],
         };
         let yaml = serde_yaml::to_string(&filter).unwrap();
         assert_eq!(
             yaml,
             indoc::indoc! {r#"
+                kind: event
                 type: .*
+                negate: false
                 attributes:
                 - key: .*
                   value: .*
+                  negate: false
             "#}
         );
     }
@@ -206,6 +674,415 @@ mod tests {
 
         assert_eq!(filter.type_str.as_str(), ".*");
         assert_eq!(filter.attributes[0].key.as_str(), ".*");
-        assert_eq!(filter.attributes[0].value.as_ref().unwrap().as_str(), ".*");
+        assert_eq!(
+            filter.attributes[0].value,
+            Some(FilterPattern::try_from(".*").unwrap().into())
+        );
+        assert_eq!(filter.kind, FilterKind::Event);
+    }
+
+    #[test]
+    fn filter_deserialize_message() {
+        let yaml = indoc::indoc! {r#"
+            kind: message
+            type: ^/cosmwasm\.wasm\.v1\.MsgExecuteContract$
+        "#};
+
+        let filter: Filter = serde_yaml::from_str(yaml).unwrap();
+
+        assert_eq!(filter.kind, FilterKind::Message);
+        assert!(filter.attributes.is_empty());
+    }
+
+    #[test]
+    fn filter_matches_message_types() {
+        use cosmos_sdk_proto::cosmos::tx::v1beta1::TxBody;
+        use prost_types::Any;
+
+        let tx = Tx {
+            body: Some(TxBody {
+                messages: vec![Any {
+                    type_url: "/cosmwasm.wasm.v1.MsgExecuteContract".to_string(),
+                    value: vec![],
+                }],
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+        .encode_to_vec();
+
+        let filter = Filter {
+            name: None,
+            kind: FilterKind::Message,
+            type_str: FilterPattern::try_from(r"^/cosmwasm\.wasm\.v1\.MsgExecuteContract$")
+                .unwrap(),
+            negate: false,
+            code: None,
+            attributes: vec![],
+        };
+        assert!(filter.matches_message_types(&tx));
+
+        let filter = Filter {
+            name: None,
+            kind: FilterKind::Message,
+            type_str: FilterPattern::try_from(r"^/cosmos\.bank\.v1beta1\.MsgSend$").unwrap(),
+            negate: false,
+            code: None,
+            attributes: vec![],
+        };
+        assert!(!filter.matches_message_types(&tx));
+
+        // Undecodable transactions never match.
+        assert!(!filter.matches_message_types(&[0xff, 0xff]));
+    }
+
+    #[test]
+    fn code_filter_deserialize() {
+        let yaml = indoc::indoc! {r#"
+            type: ^tx$
+            code: 0
+        "#};
+        let filter: Filter = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(
+            filter.code,
+            Some(CodeFilter {
+                code: 0,
+                negate: false,
+            })
+        );
+
+        let code: CodeFilter = serde_yaml::from_str(r#""!0""#).unwrap();
+        assert_eq!(
+            code,
+            CodeFilter {
+                code: 0,
+                negate: true,
+            }
+        );
+        assert_eq!(serde_yaml::to_string(&code).unwrap().trim(), "'!0'");
+
+        assert!(serde_yaml::from_str::<CodeFilter>(r#""!ok""#).is_err());
+    }
+
+    fn code_filter(code: &str) -> Filter {
+        let mut filter = filter("tx", "fee", ".*");
+        filter.code = Some(code.parse().unwrap());
+        filter
+    }
+
+    fn tx_with_code(code: u32) -> tx::Response {
+        let mut tx = transaction_response(&"A".repeat(64), 1);
+        tx.tx_result.code = code.into();
+        tx.tx_result.events = vec![event("tx", &[("fee", "1ujuno")])];
+        tx
+    }
+
+    #[test]
+    fn filter_matches_success_only() {
+        let filter = code_filter("0");
+
+        assert!(filter.matches(&tx_with_code(0)));
+        assert!(!filter.matches(&tx_with_code(5)));
+    }
+
+    #[test]
+    fn filter_matches_failure_only() {
+        let filter = code_filter("!0");
+
+        assert!(!filter.matches(&tx_with_code(0)));
+        assert!(filter.matches(&tx_with_code(5)));
+        assert!(filter.matches(&tx_with_code(11)));
+
+        // The code is checked before the events, and isn't inverted by negating the filter.
+        let mut negated = code_filter("!0");
+        negated.negate = true;
+        assert!(!negated.matches(&tx_with_code(0)));
+        assert!(!negated.matches(&tx_with_code(5)));
+        let mut tx = tx_with_code(5);
+        tx.tx_result.events = vec![];
+        assert!(negated.matches(&tx));
+    }
+
+    #[test]
+    fn filter_combinator() {
+        let filters = vec![
+            filter("message", "action", "MsgExecuteContract"),
+            filter("wasm", "_contract_address", "juno1croncat"),
+        ];
+        let message = event("message", &[("action", "MsgExecuteContract")]);
+        let wasm = event("wasm", &[("_contract_address", "juno1croncat")]);
+        let transfer = event("transfer", &[("amount", "1ujuno")]);
+
+        let cases = [
+            (vec![message.clone(), wasm.clone()], true, true),
+            (vec![message.clone(), transfer.clone()], false, true),
+            (vec![wasm.clone()], false, true),
+            (vec![transfer.clone()], false, false),
+            (vec![], false, false),
+        ];
+
+        for (events, all, any) in cases {
+            let matches = |filter: &Filter| *filter == events;
+            assert_eq!(FilterCombinator::All.combine(&filters, matches), all);
+            assert_eq!(FilterCombinator::Any.combine(&filters, matches), any);
+        }
+    }
+
+    #[test]
+    fn compiled_filter_set_matches_naive() {
+        let mut negated = filter("wasm", "_contract_address", "juno1spammy");
+        negated.negate = true;
+        let mut failed = filter("tx", "fee", ".*");
+        failed.code = Some("!0".parse().unwrap());
+        let filters = vec![
+            filter("message", "action", "MsgExecuteContract"),
+            filter("wasm", "_contract_address", "juno1croncat"),
+            negated,
+            failed,
+        ];
+
+        let events = [
+            event("message", &[("action", "MsgExecuteContract")]),
+            event("wasm", &[("_contract_address", "juno1croncat")]),
+            event("wasm", &[("_contract_address", "juno1spammy")]),
+            event("tx", &[("fee", "1ujuno")]),
+            event("transfer", &[("amount", "1ujuno")]),
+        ];
+
+        // Every subset of the events and filters, with both combinators and codes.
+        for event_mask in 0..(1 << events.len()) {
+            for code in [0u32, 5] {
+                let mut tx = transaction_response(&"A".repeat(64), 1);
+                tx.tx_result.code = code.into();
+                tx.tx_result.events = events
+                    .iter()
+                    .enumerate()
+                    .filter(|(index, _)| event_mask & (1 << index) != 0)
+                    .map(|(_, event)| event.clone())
+                    .collect();
+
+                for filter_mask in 0..(1 << filters.len()) {
+                    let filters = filters
+                        .iter()
+                        .enumerate()
+                        .filter(|(index, _)| filter_mask & (1 << index) != 0)
+                        .map(|(_, filter)| filter.clone())
+                        .collect::<Vec<_>>();
+                    for combinator in [FilterCombinator::All, FilterCombinator::Any] {
+                        assert_eq!(
+                            CompiledFilterSet::new(&filters, combinator)
+                                .unwrap()
+                                .matches(&tx),
+                            combinator.matches(&filters, &tx),
+                            "events {:b}, filters {:b}, code {}, {}",
+                            event_mask,
+                            filter_mask,
+                            code,
+                            combinator
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn compiled_filter_set_matches_many_types() {
+        use cosmos_sdk_proto::cosmos::tx::v1beta1::TxBody;
+        use prost_types::Any;
+
+        // Many filters with distinct types, some of them sharing events or overlapping patterns.
+        let mut filters = (0..20)
+            .map(|index| filter(&format!("^type{}$", index), "key", &format!("^{}$", index)))
+            .collect::<Vec<_>>();
+        filters.push(filter("^type1", "key", "^1[0-9]$"));
+        filters.push(Filter {
+            name: None,
+            kind: FilterKind::Message,
+            type_str: r"^/cosmos\.bank\.v1beta1\.MsgSend$".try_into().unwrap(),
+            negate: false,
+            code: None,
+            attributes: vec![],
+        });
+        filters.push(Filter {
+            negate: true,
+            ..filters[3].clone()
+        });
+
+        let mut tx = transaction_response(&"A".repeat(64), 1);
+        tx.tx = Tx {
+            body: Some(TxBody {
+                messages: vec![Any {
+                    type_url: "/cosmos.bank.v1beta1.MsgSend".to_string(),
+                    value: vec![],
+                }],
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+        .encode_to_vec()
+        .into();
+        tx.tx_result.events = vec![
+            event("type3", &[("key", "3")]),
+            event("type12", &[("key", "12")]),
+            event("type7", &[("key", "8")]),
+            event("other", &[("key", "1")]),
+        ];
+
+        // Each filter alone, and every window of filters, agree with matching them one by one.
+        for combinator in [FilterCombinator::All, FilterCombinator::Any] {
+            for start in 0..filters.len() {
+                for end in start..=filters.len() {
+                    let filters = &filters[start..end];
+                    assert_eq!(
+                        CompiledFilterSet::new(filters, combinator)
+                            .unwrap()
+                            .matches(&tx),
+                        combinator.matches(filters, &tx),
+                        "filters {} to {}, {}",
+                        start,
+                        end,
+                        combinator
+                    );
+                }
+            }
+        }
+
+        let matching = filters
+            .iter()
+            .enumerate()
+            .filter(|(_, filter)| {
+                CompiledFilterSet::new(&[(*filter).clone()], FilterCombinator::All)
+                    .unwrap()
+                    .matches(&tx)
+            })
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+        assert_eq!(matching, [3, 12, 20, 21]);
+    }
+
+    #[test]
+    fn filter_combinator_without_filters() {
+        assert!(FilterCombinator::All.combine(&[], |_| false));
+        assert!(FilterCombinator::Any.combine(&[], |_| false));
+    }
+
+    #[test]
+    fn filter_eq_multiple_events() {
+        let mut filter = filter("wasm", "_contract_address", "juno1croncat");
+        filter.attributes.push(AttributeFilter {
+            key: "action".try_into().unwrap(),
+            value: Some("proxy_call".try_into().unwrap()),
+            negate: false,
+        });
+
+        // The attributes are split across two events, so neither event matches on its own.
+        let events = vec![
+            event("wasm", &[("_contract_address", "juno1croncat")]),
+            event("wasm", &[("action", "proxy_call")]),
+        ];
+        assert!(filter != events);
+
+        // Both attributes in the same event match.
+        let events = vec![
+            event("message", &[("action", "MsgExecuteContract")]),
+            event(
+                "wasm",
+                &[
+                    ("_contract_address", "juno1croncat"),
+                    ("action", "proxy_call"),
+                ],
+            ),
+        ];
+        assert!(filter == events);
+
+        // The attributes must belong to an event of the right type.
+        let events = vec![event(
+            "message",
+            &[
+                ("_contract_address", "juno1croncat"),
+                ("action", "proxy_call"),
+            ],
+        )];
+        assert!(filter != events);
+    }
+
+    #[test]
+    fn filter_eq_repeated_attributes() {
+        let mut filter = filter("wasm", "action", "proxy_call");
+        filter.attributes.push(AttributeFilter {
+            key: "task_hash".try_into().unwrap(),
+            value: None,
+            negate: false,
+        });
+
+        // A repeated attribute doesn't make up for a missing one.
+        let events = vec![event(
+            "wasm",
+            &[("action", "proxy_call"), ("action", "proxy_call")],
+        )];
+        assert!(filter != events);
+
+        // Key only attribute filters match any value.
+        let events = vec![event(
+            "wasm",
+            &[
+                ("action", "proxy_call"),
+                ("action", "proxy_call"),
+                ("task_hash", "abc"),
+            ],
+        )];
+        assert!(filter == events);
+    }
+
+    #[test]
+    fn filter_eq_negated_key() {
+        let mut filter = filter("wasm", "action", "proxy_call");
+        filter.attributes.push(AttributeFilter {
+            key: "spam".try_into().unwrap(),
+            value: None,
+            negate: true,
+        });
+
+        let events = vec![event("wasm", &[("action", "proxy_call")])];
+        assert!(filter == events);
+
+        let events = vec![event("wasm", &[("action", "proxy_call"), ("spam", "yes")])];
+        assert!(filter != events);
+    }
+
+    #[test]
+    fn filter_eq_negated_key_value() {
+        let mut negated = filter("wasm", "_contract_address", "juno1spammy");
+        negated.negate = true;
+
+        let events = vec![event("wasm", &[("_contract_address", "juno1spammy")])];
+        assert!(negated != events);
+
+        let events = vec![event("wasm", &[("_contract_address", "juno1croncat")])];
+        assert!(negated == events);
+
+        // Negated attribute filters only exclude the matching value.
+        let mut attribute_negated = filter("wasm", "action", ".*");
+        attribute_negated.attributes.push(AttributeFilter {
+            key: "_contract_address".try_into().unwrap(),
+            value: Some("juno1spammy".try_into().unwrap()),
+            negate: true,
+        });
+
+        let events = vec![event(
+            "wasm",
+            &[("action", "transfer"), ("_contract_address", "juno1spammy")],
+        )];
+        assert!(attribute_negated != events);
+
+        let events = vec![event(
+            "wasm",
+            &[
+                ("action", "transfer"),
+                ("_contract_address", "juno1croncat"),
+            ],
+        )];
+        assert!(attribute_negated == events);
     }
 }
@@ -0,0 +1,137 @@
+use std::time::Duration;
+
+use enum_display::EnumDisplay;
+use serde::{Deserialize, Serialize};
+use tokio_retry::strategy::{jitter, ExponentialBackoff, FibonacciBackoff, FixedInterval};
+
+/// Backoff strategy used between retries.
+#[derive(Debug, Copy, Clone, EnumDisplay, PartialEq, Eq, Serialize, Deserialize)]
+#[enum_display(case = "Kebab")]
+#[serde(rename_all = "kebab-case")]
+pub enum RetryStrategy {
+    /// Delays grow along the fibonacci sequence.
+    Fibonacci,
+    /// Delays double on every attempt.
+    Exponential,
+    /// Delays are always the base delay.
+    Fixed,
+}
+
+/// Retry configuration for a retried operation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetryConfig {
+    /// The backoff strategy to use.
+    pub strategy: RetryStrategy,
+    /// The base delay in milliseconds.
+    #[serde(alias = "base-ms")]
+    pub base_ms: u64,
+    /// The maximum delay in milliseconds, if any.
+    #[serde(alias = "max-ms", default)]
+    pub max_ms: Option<u64>,
+    /// The maximum number of attempts.
+    #[serde(alias = "max-attempts")]
+    pub max_attempts: usize,
+}
+
+impl RetryConfig {
+    /// Create a new retry configuration.
+    pub fn new(
+        strategy: RetryStrategy,
+        base_ms: u64,
+        max_ms: Option<u64>,
+        max_attempts: usize,
+    ) -> Self {
+        Self {
+            strategy,
+            base_ms,
+            max_ms,
+            max_attempts,
+        }
+    }
+
+    /// The default retry configuration for indexing a block.
+    pub fn block_default() -> Self {
+        Self::new(RetryStrategy::Fibonacci, 100, None, 10)
+    }
+
+    /// The default retry configuration for fetching the transactions of a block.
+    pub fn transaction_default() -> Self {
+        Self::new(RetryStrategy::Fibonacci, 50, None, 15)
+    }
+
+    /// The default retry configuration for reconnecting a websocket stream.
+    pub fn reconnect_default() -> Self {
+        Self::new(RetryStrategy::Exponential, 250, Some(30_000), 10)
+    }
+
+    /// The default retry configuration for connecting to the database.
+    pub fn database_default() -> Self {
+        Self::new(RetryStrategy::Exponential, 500, Some(10_000), 10)
+    }
+
+    /// The default retry configuration for notifying a webhook.
+    pub fn webhook_default() -> Self {
+        Self::new(RetryStrategy::Exponential, 500, Some(30_000), 5)
+    }
+
+    /// Build a [`tokio_retry`] strategy from the configuration.
+    pub fn strategy(&self) -> Box<dyn Iterator<Item = Duration> + Send + Sync> {
+        let max_delay = Duration::from_millis(self.max_ms.unwrap_or(u64::MAX));
+
+        match self.strategy {
+            RetryStrategy::Fibonacci => Box::new(
+                FibonacciBackoff::from_millis(self.base_ms)
+                    .max_delay(max_delay)
+                    .map(jitter)
+                    .take(self.max_attempts),
+            ),
+            RetryStrategy::Exponential => Box::new(
+                ExponentialBackoff::from_millis(2)
+                    .factor(self.base_ms)
+                    .max_delay(max_delay)
+                    .map(jitter)
+                    .take(self.max_attempts),
+            ),
+            RetryStrategy::Fixed => Box::new(
+                FixedInterval::from_millis(self.base_ms.min(max_delay.as_millis() as u64))
+                    .take(self.max_attempts),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retry_config_deserialize() {
+        let yaml = indoc::indoc! {r#"
+            strategy: exponential
+            base-ms: 100
+            max-ms: 5000
+            max-attempts: 8
+        "#};
+
+        let retry: RetryConfig = serde_yaml::from_str(yaml).unwrap();
+
+        assert_eq!(
+            retry,
+            RetryConfig::new(RetryStrategy::Exponential, 100, Some(5000), 8)
+        );
+    }
+
+    #[test]
+    fn retry_config_strategy() {
+        let retry = RetryConfig::new(RetryStrategy::Fixed, 100, None, 3);
+        let delays = retry.strategy().collect::<Vec<_>>();
+        assert_eq!(delays, vec![Duration::from_millis(100); 3]);
+
+        let retry = RetryConfig::new(RetryStrategy::Exponential, 100, Some(1000), 6);
+        let delays = retry.strategy().collect::<Vec<_>>();
+        assert_eq!(delays.len(), 6);
+        assert!(delays
+            .iter()
+            .all(|delay| *delay <= Duration::from_millis(1000)));
+    }
+}
@@ -0,0 +1,94 @@
+use std::fmt::{self, Formatter};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use super::retry::RetryConfig;
+
+/// The default number of notifications queued for a webhook.
+pub const DEFAULT_WEBHOOK_QUEUE_SIZE: usize = 100;
+
+fn default_webhook_queue_size() -> usize {
+    DEFAULT_WEBHOOK_QUEUE_SIZE
+}
+
+/// The default number of seconds a webhook has to answer a notification.
+pub const DEFAULT_WEBHOOK_TIMEOUT_SECS: u64 = 10;
+
+fn default_webhook_timeout_secs() -> u64 {
+    DEFAULT_WEBHOOK_TIMEOUT_SECS
+}
+
+/// A webhook notified when a transaction matching some of the filters is indexed.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    /// The url notifications are POSTed to.
+    pub url: Url,
+    /// The names of the filters triggering the webhook, every indexed transaction does when empty.
+    #[serde(default)]
+    pub filters: Vec<String>,
+    /// How to retry a failed notification.
+    #[serde(default = "RetryConfig::webhook_default")]
+    pub retry: RetryConfig,
+    /// The number of pending notifications, newer ones are dropped while the queue is full.
+    #[serde(alias = "queue-size", default = "default_webhook_queue_size")]
+    pub queue_size: usize,
+    /// The number of seconds a notification may take, including connecting, before it is retried.
+    #[serde(alias = "timeout-secs", default = "default_webhook_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+impl WebhookConfig {
+    /// The webhook url without its path, which often holds a secret (e.g. Slack webhooks).
+    pub fn redacted_url(&self) -> String {
+        format!(
+            "{}://{}/***",
+            self.url.scheme(),
+            self.url.host_str().unwrap_or_default()
+        )
+    }
+
+    /// How long a notification may take before it is retried.
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs)
+    }
+}
+
+impl fmt::Debug for WebhookConfig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebhookConfig")
+            .field("url", &self.redacted_url())
+            .field("filters", &self.filters)
+            .field("retry", &self.retry)
+            .field("queue_size", &self.queue_size)
+            .field("timeout_secs", &self.timeout_secs)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn webhook_config_deserialize() {
+        let yaml = indoc::indoc! {r#"
+            url: https://hooks.slack.com/services/T000/B000/secret
+            filters:
+              - croncat
+        "#};
+
+        let webhook: WebhookConfig = serde_yaml::from_str(yaml).unwrap();
+
+        assert_eq!(webhook.filters, vec!["croncat".to_string()]);
+        assert_eq!(webhook.retry, RetryConfig::webhook_default());
+        assert_eq!(webhook.queue_size, DEFAULT_WEBHOOK_QUEUE_SIZE);
+        assert_eq!(
+            webhook.timeout(),
+            Duration::from_secs(DEFAULT_WEBHOOK_TIMEOUT_SECS)
+        );
+        assert_eq!(webhook.redacted_url(), "https://hooks.slack.com/***");
+        assert!(!format!("{:?}", webhook).contains("secret"));
+    }
+}
@@ -0,0 +1,110 @@
+use std::time::Duration;
+
+use sea_orm::DbErr;
+use snafu::Snafu;
+
+///
+/// Errors returned by the indexing library, so embedders can tell what went wrong.
+///
+/// Only the binary's runner (`system`) reports errors through `color_eyre`.
+///
+#[derive(Debug, Snafu)]
+pub enum IndexError {
+    #[snafu(display("Database error: {}", source))]
+    Database { source: DbErr },
+    #[snafu(display("Rpc error: {}", source))]
+    Rpc { source: tendermint_rpc::Error },
+    #[snafu(display("Rpc request timed out after {timeout:?}"))]
+    RpcTimeout { timeout: Duration },
+    #[snafu(display("All {clients} rpc clients failed: {}", errors.join("; ")))]
+    RpcFailover { clients: usize, errors: Vec<String> },
+    #[snafu(display("Expected chain {expected} but the rpc endpoint is on {actual}"))]
+    ChainMismatch { expected: String, actual: String },
+    #[snafu(display("No block found with hash {hash}"))]
+    BlockNotFound { hash: String },
+    #[snafu(display("Block {height} on {chain_id} is already indexed"))]
+    BlockConflict { height: i64, chain_id: String },
+    #[snafu(display("Invalid filter pattern: {}", source))]
+    Filter { source: regex::Error },
+    #[snafu(display("{message}"))]
+    Config { message: String },
+    #[snafu(display("{message}"))]
+    InvalidData { message: String },
+}
+
+impl IndexError {
+    ///
+    /// An invalid configuration.
+    ///
+    pub fn config(message: impl Into<String>) -> Self {
+        Self::Config {
+            message: message.into(),
+        }
+    }
+
+    ///
+    /// Data from the chain or the caller that can't be indexed.
+    ///
+    pub fn invalid_data(message: impl Into<String>) -> Self {
+        Self::InvalidData {
+            message: message.into(),
+        }
+    }
+}
+
+impl From<DbErr> for IndexError {
+    fn from(source: DbErr) -> Self {
+        Self::Database { source }
+    }
+}
+
+impl From<tendermint_rpc::Error> for IndexError {
+    fn from(source: tendermint_rpc::Error) -> Self {
+        Self::Rpc { source }
+    }
+}
+
+impl From<regex::Error> for IndexError {
+    fn from(source: regex::Error) -> Self {
+        Self::Filter { source }
+    }
+}
+
+/// The result of the indexing library.
+pub type IndexResult<T> = std::result::Result<T, IndexError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn index_error_display() {
+        assert_eq!(
+            IndexError::RpcFailover {
+                clients: 2,
+                errors: vec![
+                    "client 1 is down".to_string(),
+                    "client 2 is down".to_string()
+                ],
+            }
+            .to_string(),
+            "All 2 rpc clients failed: client 1 is down; client 2 is down"
+        );
+        assert_eq!(
+            IndexError::config("rpc_page_size must be at least 1").to_string(),
+            "rpc_page_size must be at least 1"
+        );
+        assert_eq!(
+            IndexError::BlockConflict {
+                height: 10,
+                chain_id: "uni-5".to_string(),
+            }
+            .to_string(),
+            "Block 10 on uni-5 is already indexed"
+        );
+        assert!(matches!(
+            IndexError::from(regex::Regex::new("(").unwrap_err()),
+            IndexError::Filter { .. }
+        ));
+    }
+}
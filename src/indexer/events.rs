@@ -0,0 +1,166 @@
+use sea_orm::prelude::Uuid;
+use sea_orm::Set;
+use tendermint::abci;
+
+use super::model::transaction_event::ActiveModel as TransactionEventModel;
+
+///
+/// The fields extracted from an event of a known type, the others are only kept in the JSON events
+/// of their transaction.
+///
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DecodedEvent {
+    pub event_type: String,
+    pub sender: Option<String>,
+    pub recipient: Option<String>,
+    pub amount: Option<String>,
+    pub contract_address: Option<String>,
+}
+
+///
+/// Get the value of the first attribute of an event with a given key.
+///
+fn attribute(event: &abci::Event, key: &str) -> Option<String> {
+    event
+        .attributes
+        .iter()
+        .find(|attribute| attribute.key.to_string() == key)
+        .map(|attribute| attribute.value.to_string())
+}
+
+///
+/// Decode a `transfer`, `message` or `wasm` event into its structured fields, other event types
+/// aren't decoded.
+///
+pub fn decode_event(event: &abci::Event) -> Option<DecodedEvent> {
+    let decoded = match event.type_str.as_str() {
+        "transfer" => DecodedEvent {
+            sender: attribute(event, "sender"),
+            recipient: attribute(event, "recipient"),
+            amount: attribute(event, "amount"),
+            ..Default::default()
+        },
+        "message" => DecodedEvent {
+            sender: attribute(event, "sender"),
+            ..Default::default()
+        },
+        "wasm" => DecodedEvent {
+            contract_address: attribute(event, "_contract_address"),
+            ..Default::default()
+        },
+        _ => return None,
+    };
+
+    Some(DecodedEvent {
+        event_type: event.type_str.clone(),
+        ..decoded
+    })
+}
+
+///
+/// Build the database entries of the known events of a transaction, keeping the index of each
+/// event among all of them.
+///
+pub fn transaction_event_models(
+    transaction_id: Uuid,
+    height: i64,
+    events: &[abci::Event],
+) -> Vec<TransactionEventModel> {
+    events
+        .iter()
+        .enumerate()
+        .filter_map(|(index, event)| {
+            let decoded = decode_event(event)?;
+            Some(TransactionEventModel {
+                id: Set(Uuid::new_v4()),
+                transaction_id: Set(transaction_id),
+                height: Set(height),
+                event_index: Set(index as i32),
+                event_type: Set(decoded.event_type),
+                sender: Set(decoded.sender),
+                recipient: Set(decoded.recipient),
+                amount: Set(decoded.amount),
+                contract_address: Set(decoded.contract_address),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indexer::tests::event;
+
+    #[test]
+    fn decode_known_events() {
+        assert_eq!(
+            decode_event(&event(
+                "transfer",
+                &[
+                    ("recipient", "juno1recipient"),
+                    ("sender", "juno1sender"),
+                    ("amount", "4000ujunox"),
+                ],
+            )),
+            Some(DecodedEvent {
+                event_type: "transfer".to_string(),
+                sender: Some("juno1sender".to_string()),
+                recipient: Some("juno1recipient".to_string()),
+                amount: Some("4000ujunox".to_string()),
+                contract_address: None,
+            })
+        );
+        assert_eq!(
+            decode_event(&event(
+                "message",
+                &[("action", "/cosmwasm.wasm.v1.MsgExecuteContract")]
+            )),
+            Some(DecodedEvent {
+                event_type: "message".to_string(),
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            decode_event(&event(
+                "wasm",
+                &[
+                    ("_contract_address", "juno1contract"),
+                    ("method", "proxy_call")
+                ],
+            )),
+            Some(DecodedEvent {
+                event_type: "wasm".to_string(),
+                contract_address: Some("juno1contract".to_string()),
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn decode_unknown_events() {
+        assert_eq!(decode_event(&event("tx", &[("fee", "4000ujunox")])), None);
+        assert_eq!(decode_event(&event("wasm-croncat", &[])), None);
+    }
+
+    #[test]
+    fn transaction_event_models_keep_event_index() {
+        let transaction_id = Uuid::new_v4();
+        let events = [
+            event("tx", &[("fee", "4000ujunox")]),
+            event("message", &[("sender", "juno1sender")]),
+            event("execute", &[("_contract_address", "juno1contract")]),
+            event("wasm", &[("_contract_address", "juno1contract")]),
+        ];
+
+        let models = transaction_event_models(transaction_id, 10, &events);
+
+        assert_eq!(models.len(), 2);
+        assert_eq!(models[0].event_index, Set(1));
+        assert_eq!(models[0].event_type, Set("message".to_string()));
+        assert_eq!(models[0].sender, Set(Some("juno1sender".to_string())));
+        assert_eq!(models[1].event_index, Set(3));
+        assert_eq!(models[1].event_type, Set("wasm".to_string()));
+        assert_eq!(models[1].transaction_id, Set(transaction_id));
+        assert_eq!(models[1].height, Set(10));
+    }
+}
@@ -0,0 +1,239 @@
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use sea_orm::{ActiveModelTrait, DatabaseConnection, EntityTrait, IntoActiveModel, Set};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, trace};
+
+use super::config::filter::CompiledFilterSet;
+use super::config::Config;
+use super::error::IndexResult;
+use super::historical::{get_min_indexed_height, BlockRange};
+use super::index_gap_range;
+use super::model::genesis_backfill::ActiveModel as GenesisBackfillModel;
+use super::model::genesis_backfill::Entity as GenesisBackfillEntity;
+use super::model::genesis_backfill::Model as GenesisBackfill;
+use super::rpc::{self, RpcPool};
+use super::sink::Sinks;
+use super::verify::request_period;
+
+///
+/// The heights to backfill from genesis, from the earliest height the nodes store (or the start
+/// height when higher) up to the lowest indexed height, or the latest one when nothing is indexed,
+/// and never past the stop height.
+///
+/// Returns `None` when the history is already indexed.
+///
+pub fn genesis_range(
+    earliest_height: i64,
+    start_height: Option<i64>,
+    stop_height: Option<i64>,
+    min_indexed_height: Option<i64>,
+    latest_height: i64,
+) -> Option<BlockRange> {
+    let start = earliest_height.max(start_height.unwrap_or(1));
+    let end = min_indexed_height
+        .map_or(latest_height, |height| height - 1)
+        .min(stop_height.unwrap_or(i64::MAX));
+
+    (start <= end).then(|| (start, end).into())
+}
+
+///
+/// The minimum time to index a number of heights at most `blocks_per_sec` per second.
+///
+pub fn chunk_duration(num_heights: u64, blocks_per_sec: u32) -> Duration {
+    request_period(blocks_per_sec) * num_heights as u32
+}
+
+///
+/// Get the genesis backfill of a chain, starting one when there is none yet and part of its
+/// history is missing.
+///
+async fn get_or_start_genesis_backfill(
+    config: &Config,
+    db: &DatabaseConnection,
+    rpc_pool: &RpcPool,
+) -> IndexResult<Option<GenesisBackfill>> {
+    let Config { name, chain_id, .. } = config;
+    if let Some(backfill) = GenesisBackfillEntity::find_by_id(chain_id.clone())
+        .one(db)
+        .await?
+    {
+        return Ok(Some(backfill));
+    }
+
+    let earliest_height = rpc_pool.with_failover(rpc::get_earliest_height).await?;
+    let latest_height = rpc_pool
+        .with_failover(rpc::get_status)
+        .await?
+        .sync_info
+        .latest_block_height
+        .value() as i64;
+    let min_indexed_height = get_min_indexed_height(db, chain_id).await?;
+    let (start, end) = match genesis_range(
+        earliest_height,
+        config.start_height,
+        config.stop_height,
+        min_indexed_height,
+        latest_height,
+    ) {
+        Some(range) => *range,
+        None => {
+            info!(
+                "[{}] The history of {} is indexed from the earliest available height {}",
+                name, chain_id, earliest_height
+            );
+            return Ok(None);
+        }
+    };
+
+    // Nothing is written in a dry run, so there is no progress to record either.
+    if config.dry_run {
+        info!(
+            "[{}] Would backfill {} from genesis, heights {} to {}",
+            name, chain_id, start, end
+        );
+        return Ok(None);
+    }
+
+    let now = Utc::now().naive_utc();
+    let backfill = GenesisBackfillModel {
+        chain_id: Set(chain_id.clone()),
+        start_height: Set(start),
+        next_height: Set(start),
+        end_height: Set(end),
+        created_at: Set(now),
+        updated_at: Set(now),
+    }
+    .insert(db)
+    .await?;
+
+    Ok(Some(backfill))
+}
+
+///
+/// Index the history of a chain from the earliest block its nodes store, in chunks of
+/// `historical_chunk_size` heights and at most `genesis_blocks_per_sec` blocks per second.
+///
+/// The next height is recorded after each chunk, so a restart resumes from it. Returns whether
+/// the history is fully indexed, or `false` when interrupted by a shutdown.
+///
+pub async fn backfill_from_genesis(
+    config: &Config,
+    rpc_pool: &RpcPool,
+    db: &DatabaseConnection,
+    sinks: &Sinks,
+    filter_set: &CompiledFilterSet,
+    shutdown: &CancellationToken,
+) -> IndexResult<bool> {
+    let Config { name, chain_id, .. } = config;
+    let mut backfill = match get_or_start_genesis_backfill(config, db, rpc_pool).await? {
+        Some(backfill) if backfill.next_height <= backfill.end_height => backfill,
+        _ => {
+            trace!("[{}] Nothing to backfill from genesis", name);
+            return Ok(true);
+        }
+    };
+    info!(
+        "[{}] Backfilling {} from genesis, heights {} to {}",
+        name, chain_id, backfill.next_height, backfill.end_height
+    );
+
+    let chunk_size = config.historical_chunk_size.max(1) as i64;
+    let mut indexed_heights = 0;
+    while backfill.next_height <= backfill.end_height {
+        let start = backfill.next_height;
+        let end = (start + chunk_size - 1).min(backfill.end_height);
+        let started = Instant::now();
+
+        let completed = index_gap_range(
+            config,
+            rpc_pool,
+            db,
+            sinks,
+            filter_set,
+            shutdown,
+            (start, end).into(),
+            &mut indexed_heights,
+        )
+        .await?;
+        if !completed {
+            return Ok(false);
+        }
+
+        let mut progress = backfill.into_active_model();
+        progress.next_height = Set(end + 1);
+        progress.updated_at = Set(Utc::now().naive_utc());
+        backfill = progress.update(db).await?;
+        info!(
+            "[{}] Backfilled {} from genesis up to {} of {}",
+            name, chain_id, end, backfill.end_height
+        );
+
+        let remaining = chunk_duration((end - start + 1) as u64, config.genesis_blocks_per_sec)
+            .saturating_sub(started.elapsed());
+        tokio::select! {
+            _ = tokio::time::sleep(remaining) => {}
+            _ = shutdown.cancelled() => return Ok(false),
+        }
+    }
+
+    info!(
+        "[{}] Backfilled the history of {} from genesis, {} blocks indexed",
+        name, chain_id, indexed_heights
+    );
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn genesis_range_of_heights() {
+        // Nothing indexed, the whole chain the node stores.
+        assert_eq!(
+            genesis_range(1, None, None, None, 500),
+            Some((1, 500).into())
+        );
+        assert_eq!(
+            genesis_range(200, None, None, None, 500),
+            Some((200, 500).into())
+        );
+
+        // The live indexer already indexed the tip.
+        assert_eq!(
+            genesis_range(200, None, None, Some(495), 500),
+            Some((200, 494).into())
+        );
+
+        // Never below the start height.
+        assert_eq!(
+            genesis_range(1, Some(300), None, None, 500),
+            Some((300, 500).into())
+        );
+
+        // Never past the stop height.
+        assert_eq!(
+            genesis_range(1, None, Some(300), None, 500),
+            Some((1, 300).into())
+        );
+        assert_eq!(
+            genesis_range(1, None, Some(300), Some(450), 500),
+            Some((1, 300).into())
+        );
+
+        // The history is already indexed.
+        assert_eq!(genesis_range(200, None, None, Some(200), 500), None);
+        assert_eq!(genesis_range(1, Some(300), None, Some(300), 500), None);
+    }
+
+    #[test]
+    fn chunk_duration_of_rate() {
+        assert_eq!(chunk_duration(100, 10), Duration::from_secs(10));
+        assert_eq!(chunk_duration(5, 50), Duration::from_millis(100));
+        assert_eq!(chunk_duration(0, 10), Duration::ZERO);
+    }
+}
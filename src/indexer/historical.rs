@@ -1,10 +1,65 @@
 use std::ops::Deref;
 
-use chrono::NaiveDateTime;
-use color_eyre::Result;
-use indoc::indoc;
-use sea_orm::{DatabaseConnection, DbBackend, FromQueryResult, Statement};
+use chrono::{Duration, NaiveDateTime};
+use enum_display::EnumDisplay;
+use indoc::{formatdoc, indoc};
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DatabaseConnection, DbBackend, EntityTrait, FromQueryResult,
+    QueryFilter, QuerySelect, Statement,
+};
 use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use super::error::IndexResult;
+use super::model::block::Column as BlockColumn;
+use super::model::block::Entity as BlockEntity;
+use super::model::skipped_block::Column as SkippedBlockColumn;
+use super::model::skipped_block::Entity as SkippedBlockEntity;
+
+/// How far back, in days, gaps in the block sequence are looked for.
+pub const GAP_LOOKBACK_DAYS: i64 = 7;
+
+/// The lookback, in days, that finds gaps over the whole indexed history of a chain.
+pub const UNBOUNDED_LOOKBACK_DAYS: i64 = 0;
+
+/// Gaps of at least this many blocks are more than a reorg or a dropped block.
+pub const MODERATE_GAP_BLOCKS: u64 = 10;
+
+/// Gaps of at least this many blocks are an outage of the indexer or its sources.
+pub const SEVERE_GAP_BLOCKS: u64 = 1000;
+
+/// Gaps that started longer ago than this many hours are one severity lower.
+pub const RECENT_GAP_HOURS: i64 = 24;
+
+///
+/// The tables refreshed by [`analyze_tables`], the ones the gap detector and lookups query.
+///
+const ANALYZED_TABLES: [&str; 2] = ["block", "transaction"];
+
+///
+/// Refresh the Postgres planner statistics of the indexed tables, so the gap detector stays fast
+/// after a large backfill without waiting for autovacuum.
+///
+/// Does nothing on other backends, returning whether the tables were analyzed.
+///
+pub async fn analyze_tables(db: &DatabaseConnection) -> IndexResult<bool> {
+    let backend = db.get_database_backend();
+    if backend != DbBackend::Postgres {
+        return Ok(false);
+    }
+
+    for table in ANALYZED_TABLES {
+        info!("Analyzing table {}", table);
+        db.execute(Statement::from_string(
+            backend,
+            format!(r#"ANALYZE "{}""#, table),
+        ))
+        .await?;
+    }
+
+    Ok(true)
+}
 
 ///
 /// A range of block heights.
@@ -26,6 +81,32 @@ impl Deref for BlockRange {
     }
 }
 
+///
+/// How much a gap matters, from its size and age.
+///
+#[derive(Debug, Copy, Clone, EnumDisplay, PartialEq, Eq, PartialOrd, Ord)]
+#[enum_display(case = "Kebab")]
+pub enum GapSeverity {
+    /// A few blocks, or a moderate gap that is no longer recent.
+    Minor,
+    /// At least [`MODERATE_GAP_BLOCKS`] blocks, or a severe gap that is no longer recent.
+    Moderate,
+    /// At least [`SEVERE_GAP_BLOCKS`] blocks within the last [`RECENT_GAP_HOURS`].
+    Severe,
+}
+
+impl GapSeverity {
+    ///
+    /// The severity one step lower, if any.
+    ///
+    fn lower(self) -> Self {
+        match self {
+            Self::Severe => Self::Moderate,
+            Self::Moderate | Self::Minor => Self::Minor,
+        }
+    }
+}
+
 ///
 /// Describe a gap in the block sequence of historical data.
 ///
@@ -38,44 +119,137 @@ pub struct BlockGap {
 
 impl BlockGap {
     ///
-    /// The SQL query to find gaps in the block sequence.
+    /// The SQL query to find gaps in the block sequence for a database backend, only among the
+    /// blocks of the lookback unless it is [`UNBOUNDED_LOOKBACK_DAYS`].
     ///
-    fn query_str() -> &'static str {
-        indoc! { r#"
-        SELECT start_time,
-               height + 1 AS start,
-               next_block - 1 AS end
-        FROM (
-            SELECT time AS start_time,
-                   height,
-                   lead(height) OVER (ORDER BY height) AS next_block
-            FROM   block
-            WHERE  chain_id = $1
-            AND    time > (NOW() - ($2 || ' day')::INTERVAL)
-        ) inner_alias
-        WHERE height + 1 <> next_block
-        ORDER BY start_time DESC;
-        "# }
+    fn query_str(backend: DbBackend, lookback_in_days: i64) -> String {
+        let bounded = lookback_in_days != UNBOUNDED_LOOKBACK_DAYS;
+        match backend {
+            DbBackend::Sqlite => formatdoc! { r#"
+            SELECT start_time,
+                   max(height + 1, ?3) AS start,
+                   next_block - 1 AS "end"
+            FROM (
+                SELECT time AS start_time,
+                       height,
+                       lead(height) OVER (ORDER BY height) AS next_block
+                FROM (
+                    SELECT time, height, chain_id FROM block
+                    UNION ALL
+                    SELECT time, height, chain_id FROM skipped_block
+                ) indexed
+                WHERE  chain_id = ?1
+                {time_filter}
+            ) inner_alias
+            WHERE height + 1 <> next_block
+            AND   next_block - 1 >= ?3
+            ORDER BY start_time DESC;
+            "#,
+                time_filter = if bounded {
+                    "AND    time > datetime('now', '-' || ?2 || ' days')"
+                } else {
+                    ""
+                },
+            },
+            _ => formatdoc! { r#"
+            SELECT start_time,
+                   GREATEST(height + 1, $3) AS start,
+                   next_block - 1 AS end
+            FROM (
+                SELECT time AS start_time,
+                       height,
+                       lead(height) OVER (ORDER BY height) AS next_block
+                FROM (
+                    SELECT time, height, chain_id FROM block
+                    UNION ALL
+                    SELECT time, height, chain_id FROM skipped_block
+                ) indexed
+                WHERE  chain_id = $1
+                {time_filter}
+            ) inner_alias
+            WHERE height + 1 <> next_block
+            AND   next_block - 1 >= $3
+            ORDER BY start_time DESC;
+            "#,
+                time_filter = if bounded {
+                    "AND    time > (NOW() - ($2 || ' day')::INTERVAL)"
+                } else {
+                    ""
+                },
+            },
+        }
         .trim()
+        .to_string()
     }
 
     ///
-    /// Find gaps in the block sequence.
+    /// Find gaps in the block sequence, starting no lower than `start_height`.
     ///
     async fn query(
         db: &DatabaseConnection,
         chain_id: String,
         lookback_in_days: i64,
-    ) -> Result<Vec<Self>> {
+        start_height: Option<i64>,
+    ) -> IndexResult<Vec<Self>> {
+        let backend = db.get_database_backend();
+
         Self::find_by_statement(Statement::from_sql_and_values(
-            DbBackend::Postgres,
-            Self::query_str(),
-            vec![chain_id.into(), lookback_in_days.to_string().into()],
+            backend,
+            &Self::query_str(backend, lookback_in_days),
+            vec![
+                chain_id.into(),
+                lookback_in_days.to_string().into(),
+                start_height.unwrap_or_default().into(),
+            ],
         ))
         .all(db)
         .await
         .map_err(|err| err.into())
     }
+
+    ///
+    /// The number of missing heights in the gap.
+    ///
+    pub fn num_heights(&self) -> u64 {
+        (self.end - self.start + 1).max(0) as u64
+    }
+
+    ///
+    /// Classify the gap by its number of missing heights, lowered by one step when it started more
+    /// than [`RECENT_GAP_HOURS`] before `now`.
+    ///
+    pub fn severity(&self, now: NaiveDateTime) -> GapSeverity {
+        let num_heights = self.num_heights();
+        let severity = if num_heights >= SEVERE_GAP_BLOCKS {
+            GapSeverity::Severe
+        } else if num_heights >= MODERATE_GAP_BLOCKS {
+            GapSeverity::Moderate
+        } else {
+            GapSeverity::Minor
+        };
+
+        if now - self.start_time > Duration::hours(RECENT_GAP_HOURS) {
+            severity.lower()
+        } else {
+            severity
+        }
+    }
+
+    ///
+    /// Split the gap into ranges of at most `chunk_size` heights.
+    ///
+    pub fn chunks(&self, chunk_size: u64) -> Vec<BlockRange> {
+        let chunk_size = chunk_size.max(1) as i64;
+
+        let mut chunks = vec![];
+        let mut start = self.start;
+        while start <= self.end {
+            let end = (start + chunk_size - 1).min(self.end);
+            chunks.push((start, end).into());
+            start = end + 1;
+        }
+        chunks
+    }
 }
 
 ///
@@ -99,12 +273,161 @@ impl Iterator for BlockGap {
 ///
 /// Get historical gaps in the block sequence for a chain within a lookback period (interval is days for now).
 ///
+/// A lookback of [`UNBOUNDED_LOOKBACK_DAYS`] finds the gaps over the whole indexed history.
+///
+/// With a `start_height`, gaps below it are left out and a gap spanning it starts at it.
+///
 pub async fn get_block_gaps(
     db: &DatabaseConnection,
     chain_id: String,
     lookback_in_days: i64,
-) -> Result<Vec<BlockGap>> {
-    BlockGap::query(db, chain_id, lookback_in_days).await
+    start_height: Option<i64>,
+) -> IndexResult<Vec<BlockGap>> {
+    BlockGap::query(db, chain_id, lookback_in_days, start_height).await
+}
+
+///
+/// Leave out the gaps above a stop height and end a gap spanning it at it, as those heights are
+/// never indexed.
+///
+pub fn clip_gaps(gaps: &mut Vec<BlockGap>, stop_height: i64) {
+    gaps.retain(|gap| gap.start <= stop_height);
+    for gap in gaps.iter_mut() {
+        gap.end = gap.end.min(stop_height);
+    }
+}
+
+///
+/// Order gaps by how soon they should be backfilled, the most severe first, then the most recent.
+///
+pub fn prioritize_gaps(gaps: &mut [BlockGap], now: NaiveDateTime) {
+    gaps.sort_by(|a, b| {
+        b.severity(now)
+            .cmp(&a.severity(now))
+            .then(b.start_time.cmp(&a.start_time))
+            .then(b.start.cmp(&a.start))
+    });
+}
+
+///
+/// The position of the gap containing a range in a list of gaps, or the number of gaps when none
+/// does.
+///
+pub fn gap_rank(gaps: &[BlockGap], range: &BlockRange) -> usize {
+    let (start, end) = **range;
+    gaps.iter()
+        .position(|gap| gap.start <= start && end <= gap.end)
+        .unwrap_or(gaps.len())
+}
+
+///
+/// Describe each gap's range, size and severity as of `now`, then the total number of missing
+/// heights and severe gaps.
+///
+pub fn gaps_report(
+    chain_id: &str,
+    lookback_in_days: i64,
+    gaps: &[BlockGap],
+    now: NaiveDateTime,
+) -> String {
+    let period = if lookback_in_days == UNBOUNDED_LOOKBACK_DAYS {
+        "the whole history".to_string()
+    } else {
+        format!("the last {} days", lookback_in_days)
+    };
+    let mut report = format!("{} gaps on {} over {}\n", gaps.len(), chain_id, period);
+    for gap in gaps {
+        let severity = gap.severity(now);
+        report.push_str(&format!(
+            "{}{}-{}: {} blocks (after {}), {}\n",
+            if severity == GapSeverity::Severe {
+                "! "
+            } else {
+                ""
+            },
+            gap.start,
+            gap.end,
+            gap.num_heights(),
+            gap.start_time,
+            severity
+        ));
+    }
+    let missing = gaps.iter().map(BlockGap::num_heights).sum::<u64>();
+    let severe = gaps
+        .iter()
+        .filter(|gap| gap.severity(now) == GapSeverity::Severe)
+        .count();
+    report.push_str(&format!(
+        "{} blocks missing, {} severe gaps\n",
+        missing, severe
+    ));
+    report
+}
+
+///
+/// The highest (or lowest) height of a query, if any rows matched.
+///
+#[derive(Debug, FromQueryResult)]
+struct HeightBound {
+    height: Option<i64>,
+}
+
+///
+/// Get the highest indexed (or skipped) height of a chain, if any block was indexed yet.
+///
+pub async fn get_max_indexed_height(
+    db: &DatabaseConnection,
+    chain_id: &str,
+) -> IndexResult<Option<i64>> {
+    let block = BlockEntity::find()
+        .select_only()
+        .column_as(Expr::col(BlockColumn::Height).max(), "height")
+        .filter(BlockColumn::ChainId.eq(chain_id))
+        .into_model::<HeightBound>()
+        .one(db)
+        .await?;
+    let skipped_block = SkippedBlockEntity::find()
+        .select_only()
+        .column_as(Expr::col(SkippedBlockColumn::Height).max(), "height")
+        .filter(SkippedBlockColumn::ChainId.eq(chain_id))
+        .into_model::<HeightBound>()
+        .one(db)
+        .await?;
+
+    Ok([block, skipped_block]
+        .into_iter()
+        .flatten()
+        .filter_map(|max| max.height)
+        .max())
+}
+
+///
+/// Get the lowest indexed (or skipped) height of a chain, if any block was indexed yet.
+///
+pub async fn get_min_indexed_height(
+    db: &DatabaseConnection,
+    chain_id: &str,
+) -> IndexResult<Option<i64>> {
+    let block = BlockEntity::find()
+        .select_only()
+        .column_as(Expr::col(BlockColumn::Height).min(), "height")
+        .filter(BlockColumn::ChainId.eq(chain_id))
+        .into_model::<HeightBound>()
+        .one(db)
+        .await?;
+    let skipped_block = SkippedBlockEntity::find()
+        .select_only()
+        .column_as(Expr::col(SkippedBlockColumn::Height).min(), "height")
+        .filter(SkippedBlockColumn::ChainId.eq(chain_id))
+        .into_model::<HeightBound>()
+        .one(db)
+        .await?;
+
+    Ok([block, skipped_block]
+        .into_iter()
+        .flatten()
+        .filter_map(|min| min.height)
+        .min())
 }
 
 #[cfg(test)]
@@ -123,4 +446,188 @@ mod tests {
         assert_eq!(block_gap.next(), Some((3, 3).into()));
         assert_eq!(block_gap.next(), None);
     }
+
+    #[test]
+    fn block_gap_query_str() {
+        let postgres = BlockGap::query_str(DbBackend::Postgres, GAP_LOOKBACK_DAYS);
+        assert!(postgres.contains("$1") && postgres.contains("::INTERVAL"));
+
+        let sqlite = BlockGap::query_str(DbBackend::Sqlite, GAP_LOOKBACK_DAYS);
+        assert!(sqlite.contains("?1") && sqlite.contains("datetime('now'"));
+        assert!(!sqlite.contains("::"));
+
+        // Both clamp the gaps to the start height.
+        assert!(postgres.contains("GREATEST(height + 1, $3)"));
+        assert!(sqlite.contains("max(height + 1, ?3)"));
+    }
+
+    #[test]
+    fn block_gap_query_str_unbounded() {
+        let postgres = BlockGap::query_str(DbBackend::Postgres, UNBOUNDED_LOOKBACK_DAYS);
+        assert!(!postgres.contains("time >"));
+        assert!(!postgres.contains("$2"));
+
+        let sqlite = BlockGap::query_str(DbBackend::Sqlite, UNBOUNDED_LOOKBACK_DAYS);
+        assert!(!sqlite.contains("time >"));
+        assert!(!sqlite.contains("?2"));
+    }
+
+    #[test]
+    fn block_gap_num_heights() {
+        let block_gap = BlockGap {
+            start_time: NaiveDateTime::from_timestamp(0, 0),
+            start: 5,
+            end: 9,
+        };
+        assert_eq!(block_gap.num_heights(), 5);
+
+        let block_gap = BlockGap {
+            start_time: NaiveDateTime::from_timestamp(0, 0),
+            start: 5,
+            end: 5,
+        };
+        assert_eq!(block_gap.num_heights(), 1);
+    }
+
+    #[test]
+    fn clip_gaps_to_stop_height() {
+        let gap = |start, end| BlockGap {
+            start_time: NaiveDateTime::from_timestamp(0, 0),
+            start,
+            end,
+        };
+        let mut gaps = vec![gap(3, 5), gap(10, 20), gap(30, 40)];
+
+        clip_gaps(&mut gaps, 15);
+        assert_eq!(
+            gaps.iter()
+                .map(|gap| (gap.start, gap.end))
+                .collect::<Vec<_>>(),
+            [(3, 5), (10, 15)]
+        );
+    }
+
+    #[test]
+    fn gaps_report_totals_missing_heights() {
+        let gaps = vec![
+            BlockGap {
+                start_time: NaiveDateTime::from_timestamp(0, 0),
+                start: 2000,
+                end: 2999,
+            },
+            BlockGap {
+                start_time: NaiveDateTime::from_timestamp(0, 0),
+                start: 10,
+                end: 14,
+            },
+            BlockGap {
+                start_time: NaiveDateTime::from_timestamp(0, 0),
+                start: 3,
+                end: 3,
+            },
+        ];
+
+        let now = NaiveDateTime::from_timestamp(0, 0);
+
+        assert_eq!(
+            gaps_report("uni-5", 7, &gaps, now),
+            indoc! {"
+                3 gaps on uni-5 over the last 7 days
+                ! 2000-2999: 1000 blocks (after 1970-01-01 00:00:00), severe
+                10-14: 5 blocks (after 1970-01-01 00:00:00), minor
+                3-3: 1 blocks (after 1970-01-01 00:00:00), minor
+                1006 blocks missing, 1 severe gaps
+            "}
+        );
+        assert_eq!(
+            gaps_report("uni-5", 7, &[], now),
+            "0 gaps on uni-5 over the last 7 days\n0 blocks missing, 0 severe gaps\n"
+        );
+        assert_eq!(
+            gaps_report("uni-5", UNBOUNDED_LOOKBACK_DAYS, &[], now),
+            "0 gaps on uni-5 over the whole history\n0 blocks missing, 0 severe gaps\n"
+        );
+    }
+
+    fn gap(hours_ago: i64, start: i64, end: i64) -> BlockGap {
+        BlockGap {
+            start_time: NaiveDateTime::from_timestamp(0, 0) - Duration::hours(hours_ago),
+            start,
+            end,
+        }
+    }
+
+    #[test]
+    fn block_gap_severity() {
+        let now = NaiveDateTime::from_timestamp(0, 0);
+
+        // Recent gaps are classified by their size.
+        assert_eq!(gap(1, 100, 101).severity(now), GapSeverity::Minor);
+        assert_eq!(gap(1, 100, 109).severity(now), GapSeverity::Moderate);
+        assert_eq!(gap(1, 100, 1098).severity(now), GapSeverity::Moderate);
+        assert_eq!(gap(1, 100, 1099).severity(now), GapSeverity::Severe);
+        assert_eq!(gap(24, 100, 10099).severity(now), GapSeverity::Severe);
+
+        // Older ones are one step lower.
+        assert_eq!(gap(25, 100, 10099).severity(now), GapSeverity::Moderate);
+        assert_eq!(gap(48, 100, 109).severity(now), GapSeverity::Minor);
+        assert_eq!(gap(48, 100, 100).severity(now), GapSeverity::Minor);
+
+        assert!(GapSeverity::Severe > GapSeverity::Moderate);
+        assert!(GapSeverity::Moderate > GapSeverity::Minor);
+        assert_eq!(GapSeverity::Severe.to_string(), "severe");
+    }
+
+    #[test]
+    fn prioritize_gaps_severe_and_recent_first() {
+        let now = NaiveDateTime::from_timestamp(0, 0);
+        let mut gaps = vec![
+            gap(1, 5000, 5001),
+            gap(72, 100, 20099),
+            gap(2, 3000, 4999),
+            gap(3, 1000, 1019),
+            gap(1, 6000, 7999),
+        ];
+        prioritize_gaps(&mut gaps, now);
+
+        assert_eq!(
+            gaps.iter().map(|gap| gap.start).collect::<Vec<_>>(),
+            // Severe gaps first, then moderate ones by recency even when an older one is larger.
+            vec![6000, 3000, 1000, 100, 5000]
+        );
+    }
+
+    #[test]
+    fn gap_rank_of_ranges() {
+        let gaps = vec![gap(1, 100, 199), gap(2, 10, 19)];
+
+        assert_eq!(gap_rank(&gaps, &(100, 149).into()), 0);
+        assert_eq!(gap_rank(&gaps, &(150, 199).into()), 0);
+        assert_eq!(gap_rank(&gaps, &(10, 19).into()), 1);
+        assert_eq!(gap_rank(&gaps, &(50, 59).into()), 2);
+    }
+
+    #[test]
+    fn block_gap_chunks() {
+        let block_gap = BlockGap {
+            start_time: NaiveDateTime::from_timestamp(0, 0),
+            start: 1,
+            end: 1000,
+        };
+        let chunks = block_gap.chunks(100);
+        assert_eq!(chunks.len(), 10);
+        assert_eq!(chunks.first(), Some(&(1, 100).into()));
+        assert_eq!(chunks.last(), Some(&(901, 1000).into()));
+
+        let chunks = block_gap.chunks(300);
+        assert_eq!(chunks.len(), 4);
+        assert_eq!(chunks.last(), Some(&(901, 1000).into()));
+    }
+
+    #[tokio::test]
+    async fn analyze_tables_skips_sqlite() {
+        let db = sea_orm::Database::connect("sqlite::memory:").await.unwrap();
+
+        assert!(!analyze_tables(&db).await.unwrap());
+    }
 }
@@ -1,32 +1,77 @@
+use std::future::Future;
 use std::time::Duration;
 
 use color_eyre::Report;
 use color_eyre::{eyre::eyre, Result};
+use cosmrs::tx::SignerPublicKey;
+use futures::stream::{self, StreamExt, TryStreamExt};
 use sea_orm::entity::prelude::*;
-use sea_orm::Set;
+use sea_orm::sea_query::{OnConflict, Query, SelectStatement};
+use sea_orm::{sqlx, ConnectionTrait, DbBackend, RuntimeErr, Set, Statement, TransactionTrait};
+use sha2::{Digest, Sha256};
 use snafu::Snafu;
 use tendermint::abci;
+use tendermint::block::CommitSig;
 use tendermint_rpc::endpoint::tx;
-use tendermint_rpc::HttpClient;
-use tokio::time::timeout;
-use tokio_retry::strategy::{jitter, FibonacciBackoff};
 use tokio_retry::Retry;
-use tracing::{info, trace};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, trace, warn};
 
-use self::config::filter::Filter;
-use self::historical::get_block_gaps;
+use self::config::filter::CompiledFilterSet;
+use self::config::{Config, ConflictMode};
+pub use self::error::{IndexError, IndexResult};
+use self::historical::{
+    clip_gaps, gap_rank, get_block_gaps, get_max_indexed_height, prioritize_gaps, BlockRange,
+    GapSeverity, GAP_LOOKBACK_DAYS,
+};
+use self::rpc::{RpcPool, TransactionPage};
+use self::sink::Sinks;
 use crate::streams::block::Block;
 // Sane model aliases
 use self::model::block::Model as DatabaseBlock;
 use model::block::ActiveModel as BlockModel;
+use model::block::Column as BlockColumn;
+use model::block::Entity as BlockEntity;
+use model::block_signature::ActiveModel as BlockSignatureModel;
+use model::block_signature::Column as BlockSignatureColumn;
+use model::block_signature::Entity as BlockSignatureEntity;
+use model::skipped_block::ActiveModel as SkippedBlockModel;
+use model::skipped_block::Column as SkippedBlockColumn;
+use model::skipped_block::Entity as SkippedBlockEntity;
 use model::transaction::ActiveModel as TransactionModel;
+use model::transaction::Column as TransactionColumn;
+use model::transaction::Entity as TransactionEntity;
+use model::transaction_event::Entity as TransactionEventEntity;
 
+#[cfg(feature = "api")]
+pub mod api;
+pub mod backfill;
 pub mod config;
+pub mod error;
+pub mod events;
+pub mod genesis;
 pub mod historical;
 #[allow(clippy::all)]
 pub mod model; // Tell clippy to ignore the generated model code.
+pub mod prune;
 pub mod rpc;
+pub mod show;
+pub mod sink;
+pub mod status;
 pub mod system;
+pub mod verify;
+#[cfg(feature = "watch")]
+pub mod watch;
+pub mod webhook;
+
+/// The maximum number of transaction pages fetched at once for a single block.
+const MAX_CONCURRENT_PAGE_REQUESTS: usize = 4;
+
+/// The maximum number of recent blocks indexed when catching up on startup.
+const MAX_CATCH_UP_BLOCKS: i64 = 10_000;
+
+/// The Postgres SQLSTATE and SQLite extended result codes for unique and primary key violations.
+const UNIQUE_VIOLATION_CODES: [&str; 3] = ["23505", "2067", "1555"];
 
 ///
 /// Block errors.
@@ -43,33 +88,224 @@ pub enum BlockError {
     EventWithoutBlock,
     #[snafu(display("Block stream recv error {source}"))]
     TendermintError { source: tendermint_rpc::Error },
+    #[snafu(display("Websocket driver stopped: {source}"))]
+    DriverStopped { source: Report },
     #[snafu(display("Unexpected error {source}"))]
     UnexpectedError { source: Report },
+    #[snafu(display("Gave up reconnecting after {attempts} attempts: {source}"))]
+    Reconnect { attempts: usize, source: Report },
+    #[snafu(display("Failed to read blocks from {path}: {source}"))]
+    ReadFile { path: String, source: Report },
+    #[snafu(display("Invalid block on line {line} of {path}: {source}"))]
+    InvalidFileBlock {
+        path: String,
+        line: usize,
+        source: Report,
+    },
+}
+
+///
+/// Parse an RFC 3339 block time into a database timestamp in UTC.
+///
+fn parse_block_time(time: &str) -> IndexResult<DateTime> {
+    chrono::DateTime::parse_from_rfc3339(time)
+        .map(|time| time.naive_utc())
+        .map_err(|err| IndexError::invalid_data(format!("Invalid block time {}: {}", time, err)))
+}
+
+///
+/// Get the time of a block as a database timestamp.
+///
+fn block_time(block: &Block) -> IndexResult<DateTime> {
+    parse_block_time(block.header().time.to_rfc3339().as_str())
 }
 
 ///
 /// Create a block database entry from a block.
 ///
-impl From<Block> for BlockModel {
-    fn from(block: Block) -> Self {
+impl TryFrom<Block> for BlockModel {
+    type Error = IndexError;
+
+    fn try_from(block: Block) -> IndexResult<Self> {
         let height: i64 = block.header().height.into();
         let chain_id = block.header().chain_id.to_string();
-        let time = DateTime::parse_from_str(
-            block.header().time.to_rfc3339().as_str(),
-            "%Y-%m-%dT%H:%M:%S%.f%Z",
-        )
-        .unwrap();
+        let time = block_time(&block)?;
         let hash = block.header().hash().to_string();
         let num_txs = block.data().as_ref().iter().count() as i64;
+        let proposer_address = block.header().proposer_address.to_string();
+        let app_hash = block.header().app_hash.to_string();
+        let last_commit_hash = block
+            .header()
+            .last_commit_hash
+            .as_ref()
+            .map(|hash| hash.to_string());
+        let participation = last_commit_participation(&block);
 
-        Self {
+        Ok(Self {
             id: Set(Uuid::new_v4()),
             height: Set(height),
             chain_id: Set(chain_id),
             time: Set(time),
             hash: Set(hash),
             num_txs: Set(num_txs),
-        }
+            proposer_address: Set(Some(proposer_address)),
+            app_hash: Set(Some(app_hash)),
+            last_commit_hash: Set(last_commit_hash),
+            total_gas_used: Set(0),
+            total_gas_wanted: Set(0),
+            source: Set(block.source),
+            last_commit_signatures: Set(participation.map(|(signatures, _)| signatures)),
+            last_commit_validators: Set(participation.map(|(_, validators)| validators)),
+        })
+    }
+}
+
+///
+/// The number of validators that signed the last commit of a block for it, and the number of
+/// validators in the commit, if the block has a last commit.
+///
+fn last_commit_participation(block: &Block) -> Option<(i32, i32)> {
+    block.last_commit().as_ref().map(|commit| {
+        let signatures = commit
+            .signatures
+            .iter()
+            .filter(|signature| matches!(signature, CommitSig::BlockIdFlagCommit { .. }))
+            .count();
+        (signatures as i32, commit.signatures.len() as i32)
+    })
+}
+
+///
+/// Create the signature database entries of the last commit of a block, at the height it commits.
+///
+/// Validators absent from the commit have no address in it, so they are only counted by
+/// [`last_commit_participation`].
+///
+fn block_signature_models(block: &Block) -> IndexResult<Vec<BlockSignatureModel>> {
+    let commit = match block.last_commit() {
+        Some(commit) => commit,
+        None => return Ok(vec![]),
+    };
+    let height: i64 = commit.height.into();
+    let chain_id = block.header().chain_id.to_string();
+
+    commit
+        .signatures
+        .iter()
+        .filter_map(|signature| match signature {
+            CommitSig::BlockIdFlagAbsent => None,
+            CommitSig::BlockIdFlagCommit {
+                validator_address,
+                timestamp,
+                ..
+            } => Some(("commit", validator_address, timestamp)),
+            CommitSig::BlockIdFlagNil {
+                validator_address,
+                timestamp,
+                ..
+            } => Some(("nil", validator_address, timestamp)),
+        })
+        .map(|(block_id_flag, validator_address, timestamp)| {
+            Ok(BlockSignatureModel {
+                height: Set(height),
+                chain_id: Set(chain_id.clone()),
+                validator_address: Set(validator_address.to_string()),
+                block_id_flag: Set(block_id_flag.to_string()),
+                time: Set(parse_block_time(&timestamp.to_rfc3339())?),
+            })
+        })
+        .collect()
+}
+
+///
+/// Save the signatures of the last commit of a block, replacing the votes already saved for its
+/// validators at that height.
+///
+async fn save_block_signatures(db: &DatabaseConnection, block: &Block) -> IndexResult<()> {
+    let signatures = block_signature_models(block)?;
+    if signatures.is_empty() {
+        return Ok(());
+    }
+
+    BlockSignatureEntity::insert_many(signatures)
+        .on_conflict(
+            OnConflict::columns([
+                BlockSignatureColumn::Height,
+                BlockSignatureColumn::ChainId,
+                BlockSignatureColumn::ValidatorAddress,
+            ])
+            .update_columns([
+                BlockSignatureColumn::BlockIdFlag,
+                BlockSignatureColumn::Time,
+            ])
+            .to_owned(),
+        )
+        .exec_without_returning(db)
+        .await?;
+
+    Ok(())
+}
+
+///
+/// Create a skipped block database entry from a block.
+///
+impl TryFrom<Block> for SkippedBlockModel {
+    type Error = IndexError;
+
+    fn try_from(block: Block) -> IndexResult<Self> {
+        let height: i64 = block.header().height.into();
+        let chain_id = block.header().chain_id.to_string();
+        let time = block_time(&block)?;
+
+        Ok(Self {
+            height: Set(height),
+            chain_id: Set(chain_id),
+            time: Set(time),
+        })
+    }
+}
+
+///
+/// The fee and first signer of a raw transaction.
+///
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct FeeAndSigner {
+    fee_amount: Option<String>,
+    fee_denom: Option<String>,
+    signer: Option<String>,
+}
+
+impl FeeAndSigner {
+    ///
+    /// Decode the fee and first signer of a raw `cosmos.tx.v1beta1.Tx`.
+    ///
+    /// Only the first coin of the fee is kept, and the signer is only known for single key
+    /// signers when the account prefix is configured.
+    ///
+    fn decode(tx: &[u8], account_prefix: Option<&str>) -> Result<Self> {
+        let tx = cosmrs::Tx::from_bytes(tx).map_err(|err| eyre!("{}", err))?;
+        let fee = tx.auth_info.fee.amount.first();
+        let signer = match (
+            account_prefix,
+            tx.auth_info
+                .signer_infos
+                .first()
+                .and_then(|signer_info| signer_info.public_key.as_ref()),
+        ) {
+            (Some(prefix), Some(SignerPublicKey::Single(public_key))) => Some(
+                public_key
+                    .account_id(prefix)
+                    .map_err(|err| eyre!("{}", err))?
+                    .to_string(),
+            ),
+            _ => None,
+        };
+
+        Ok(Self {
+            fee_amount: fee.map(|coin| coin.amount.to_string()),
+            fee_denom: fee.map(|coin| coin.denom.to_string()),
+            signer,
+        })
     }
 }
 
@@ -80,15 +316,38 @@ impl TransactionModel {
     ///
     /// Convert a transaction into a database entry.
     ///
-    fn from_response(block_id: Uuid, transaction: tx::Response) -> Result<Self> {
+    /// The fee and signer are left empty when the raw transaction can't be decoded, and the JSON
+    /// events when `store_events_json` is off.
+    ///
+    fn from_response(
+        block_id: Uuid,
+        transaction: tx::Response,
+        account_prefix: Option<&str>,
+        store_events_json: bool,
+    ) -> IndexResult<Self> {
         let hash = transaction.hash.to_string();
         let code = transaction.tx_result.code.value() as i32;
         let height = transaction.height.value() as i64;
         let gas_wanted = transaction.tx_result.gas_wanted.to_string();
         let gas_used = transaction.tx_result.gas_used.to_string();
-        let events = Self::decode_events(transaction.tx_result.events)?;
+        let events = if store_events_json {
+            Some(Self::decode_events(transaction.tx_result.events)?)
+        } else {
+            None
+        };
         let log = transaction.tx_result.log.to_string();
         let info = transaction.tx_result.info.to_string();
+        let FeeAndSigner {
+            fee_amount,
+            fee_denom,
+            signer,
+        } = FeeAndSigner::decode(transaction.tx.as_bytes(), account_prefix).unwrap_or_else(|err| {
+            warn!(
+                "Failed to decode the fee and signer of transaction {}: {}",
+                hash, err
+            );
+            FeeAndSigner::default()
+        });
 
         Ok(Self {
             id: Set(Uuid::new_v4()),
@@ -101,13 +360,16 @@ impl TransactionModel {
             events: Set(events),
             log: Set(log),
             info: Set(info),
+            fee_amount: Set(fee_amount),
+            fee_denom: Set(fee_denom),
+            signer: Set(signer),
         })
     }
 
     ///
     /// Decode events from a transaction.
     ///
-    fn decode_events(events: Vec<abci::Event>) -> Result<serde_json::Value> {
+    fn decode_events(events: Vec<abci::Event>) -> IndexResult<serde_json::Value> {
         let mut decoded_events = Vec::new();
         for event in events {
             let decoded_attributes: Vec<serde_json::Value> = event
@@ -142,163 +404,1811 @@ impl TransactionModel {
 }
 
 ///
-/// Index a block into the database.
+/// Index a block into the database, returning the number of its transactions matching the filters.
 ///
 pub async fn index_block(
+    config: &Config,
     db: &DatabaseConnection,
-    rpc_client: &HttpClient,
-    filters: &[Filter],
+    rpc_pool: &RpcPool,
+    sinks: &Sinks,
+    filter_set: &CompiledFilterSet,
     block: Block,
-) -> Result<()> {
-    let block_insert_result = BlockModel::from(block).insert(db).await;
+) -> IndexResult<usize> {
+    let height: i64 = block.header().height.into();
+    if !config.indexes_height(height) {
+        trace!(
+            "[{}] Skipping block {} outside the start and stop heights",
+            config.name,
+            height
+        );
+        return Ok(0);
+    }
+    let num_txs = block.data().as_ref().iter().count();
 
-    match block_insert_result {
-        Ok(block) => {
-            // If we have transactions to index, do so.
-            if block.num_txs > 0 {
-                let retry_strategy = FibonacciBackoff::from_millis(50).map(jitter).take(15);
+    // Fetch the transactions before writing anything, so a failed fetch never leaves a block without them.
+    // Without `index_transactions` the block still records how many it has, but none are fetched and
+    // its gas totals are left at 0.
+    let (txs, (total_gas_used, total_gas_wanted)) = if num_txs > 0 && config.index_transactions {
+        // Retry the transaction query with the configured strategy.
+        let txs = Retry::spawn(config.transaction_retry.strategy(), || async {
+            get_block_transactions(config, rpc_pool, &block).await
+        })
+        .await?;
+        filter_block_transactions(config, filter_set, txs)
+    } else {
+        (vec![], (0, 0))
+    };
 
-                // Retry the transaction query up to 10 times.
-                Retry::spawn(retry_strategy, || async {
-                    index_transactions_for_block(db, rpc_client, filters, &block).await
-                })
-                .await?;
-            }
-        }
-        Err(err) => {
-            match err {
-                // If the block already exists, we can safely ignore the error.
-                DbErr::Query(message) => {
-                    if message
-                        .to_string()
-                        .contains("duplicate key value violates unique constraint")
-                    {
+    // Report what would be written, after fetching so the filters run against live data.
+    if config.dry_run {
+        let hashes = txs.iter().map(|tx| tx.hash.to_string()).collect::<Vec<_>>();
+        info!(
+            "[{}] Dry run, would index block {} with {} matching transactions: {:?}",
+            config.name,
+            height,
+            txs.len(),
+            hashes
+        );
+        return Ok(txs.len());
+    }
+
+    // Validator participation doesn't depend on the block's transactions.
+    if config.index_signatures {
+        save_block_signatures(db, &block).await?;
+    }
+
+    // Only record the height of blocks without matching transactions, so they aren't seen as gaps.
+    if config.skip_empty_blocks && txs.is_empty() {
+        trace!("Skipping block {} without matching transactions", height);
+        SkippedBlockEntity::insert(SkippedBlockModel::try_from(block)?)
+            .on_conflict(
+                OnConflict::columns([SkippedBlockColumn::Height, SkippedBlockColumn::ChainId])
+                    .do_nothing()
+                    .to_owned(),
+            )
+            .exec_without_returning(db)
+            .await?;
+        return Ok(0);
+    }
+
+    // Only publish what was written, so an already indexed block isn't published again.
+    let matched = txs.len();
+    let mut block = BlockModel::try_from(block)?;
+    block.total_gas_used = Set(total_gas_used);
+    block.total_gas_wanted = Set(total_gas_wanted);
+    if let Some((block, txs)) = save_block(config, db, block, txs).await? {
+        sinks.publish(&block, &txs).await;
+    }
+
+    Ok(matched)
+}
+
+///
+/// Save a block and its transactions together.
+///
+/// An already indexed block at the same height is replaced along with its transactions when reorgs
+/// are handled and its hash differs. Otherwise the configured [`ConflictMode`] keeps it, replaces it
+/// or fails.
+///
+/// Returns the saved block and its newly inserted transactions, if the block was written.
+///
+pub async fn save_block<C>(
+    config: &Config,
+    db: &C,
+    block: BlockModel,
+    txs: Vec<tx::Response>,
+) -> IndexResult<Option<(DatabaseBlock, Vec<tx::Response>)>>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let db_transaction = db.begin().await?;
+
+    if config.handle_reorgs || config.on_conflict != ConflictMode::Skip {
+        let height = *block.height.as_ref();
+        let chain_id = block.chain_id.as_ref().to_owned();
+        let existing = BlockEntity::find_by_id((height, chain_id.clone()))
+            .one(&db_transaction)
+            .await?;
+
+        if let Some(existing) = existing {
+            if config.handle_reorgs && existing.hash != *block.hash.as_ref() {
+                warn!(
+                    "Reorg detected at height {} on {}, replacing block {} with {}",
+                    height,
+                    chain_id,
+                    existing.hash,
+                    block.hash.as_ref()
+                );
+            } else {
+                match config.on_conflict {
+                    ConflictMode::Skip => {
                         trace!("Block already exists in database, skipping");
-                    } else {
-                        return Err(eyre!("Failed to insert block: {}", message));
+                        return Ok(None);
+                    }
+                    ConflictMode::Fail => {
+                        return Err(IndexError::BlockConflict { height, chain_id })
+                    }
+                    ConflictMode::Overwrite => {
+                        trace!("Block already exists in database, overwriting");
                     }
                 }
-                // Otherwise we should bubble up the error.
-                _ => return Err(err.into()),
             }
+
+            TransactionEntity::delete_many()
+                .filter(TransactionColumn::BlockId.eq(existing.id))
+                .exec(&db_transaction)
+                .await?;
+
+            // Keep the id of the replaced block so it stays stable for consumers.
+            let mut block = block;
+            block.id = Set(existing.id);
+            let block = block.update(&db_transaction).await?;
+            let txs = index_transactions_for_block(
+                &db_transaction,
+                &block,
+                txs,
+                config.account_prefix.as_deref(),
+                config.store_events_json,
+            )
+            .await?;
+            if config.notify_blocks {
+                notify_block_indexed(&db_transaction, &block).await?;
+            }
+            db_transaction.commit().await?;
+
+            return Ok(Some((block, txs)));
         }
     }
 
-    Ok(())
+    match block.insert(&db_transaction).await {
+        Ok(block) => {
+            let txs = index_transactions_for_block(
+                &db_transaction,
+                &block,
+                txs,
+                config.account_prefix.as_deref(),
+                config.store_events_json,
+            )
+            .await?;
+            if config.notify_blocks {
+                notify_block_indexed(&db_transaction, &block).await?;
+            }
+            db_transaction.commit().await?;
+            Ok(Some((block, txs)))
+        }
+        // If the block already exists, we can safely ignore the error when skipping conflicts.
+        // Otherwise it was written meanwhile, retrying finds it to overwrite or fail.
+        Err(err) if is_unique_violation(&err) && config.on_conflict == ConflictMode::Skip => {
+            trace!("Block already exists in database, skipping");
+            Ok(None)
+        }
+        // Otherwise we should bubble up the error.
+        Err(err) => Err(err.into()),
+    }
 }
 
 ///
-/// Get transactions from a block.
+/// The Postgres channel notified of each block written when `notify_blocks` is set.
 ///
-pub async fn index_transactions_for_block(
-    db: &DatabaseConnection,
-    rpc_client: &HttpClient,
-    filters: &[Filter],
-    block: &DatabaseBlock,
-) -> Result<()> {
-    trace!("Fetching transactions for block {}", block.height);
+pub const BLOCK_INDEXED_CHANNEL: &str = "block_indexed";
 
-    let poll_timeout_duration = Duration::from_secs(60);
-    let mut found_txs = 0;
-    let mut current_page = 0;
+///
+/// The payload of a [`BLOCK_INDEXED_CHANNEL`] notification, `<chain_id>:<height>`.
+///
+pub fn block_indexed_payload(chain_id: &str, height: i64) -> String {
+    format!("{}:{}", chain_id, height)
+}
 
-    let mut txs = vec![];
+///
+/// Notify the listeners of [`BLOCK_INDEXED_CHANNEL`] of a block, does nothing on other backends
+/// than Postgres.
+///
+/// Postgres only delivers the notification when the transaction it is sent in commits, so
+/// listeners never hear of a block that was rolled back.
+///
+async fn notify_block_indexed<C>(db: &C, block: &DatabaseBlock) -> IndexResult<()>
+where
+    C: ConnectionTrait,
+{
+    let backend = db.get_database_backend();
+    if backend != DbBackend::Postgres {
+        return Ok(());
+    }
 
-    // Handle pagination of transactions.
-    while found_txs < block.num_txs {
-        current_page += 1;
+    // Same as `NOTIFY`, which can't take bound parameters.
+    db.execute(Statement::from_sql_and_values(
+        backend,
+        "SELECT pg_notify($1, $2)",
+        vec![
+            BLOCK_INDEXED_CHANNEL.into(),
+            block_indexed_payload(&block.chain_id, block.height).into(),
+        ],
+    ))
+    .await?;
 
-        // Get transactions for block from RPC.
-        let page_txs = timeout(
-            poll_timeout_duration,
-            rpc::get_transactions_for_block(rpc_client, block.height, current_page),
-        )
-        .await?
-        .map_err(|e| {
-            eyre!(
-                "Failed to get transactions for height {}: {}",
-                block.height,
-                e
-            )
-        })?;
+    Ok(())
+}
 
-        // Error if we didn't find any transactions, when we should have.
-        if page_txs.is_empty() {
-            return Err(eyre!(
-                "No transactions found from RPC for block with transactions {}",
-                block.height
-            ));
+///
+/// Check whether a database error is a unique constraint violation.
+///
+fn is_unique_violation(err: &DbErr) -> bool {
+    match err {
+        DbErr::Exec(RuntimeErr::SqlxError(sqlx::Error::Database(err)))
+        | DbErr::Query(RuntimeErr::SqlxError(sqlx::Error::Database(err))) => {
+            err.code().map_or(false, |code| {
+                UNIQUE_VIOLATION_CODES.contains(&code.as_ref())
+            })
         }
+        _ => false,
+    }
+}
+
+///
+/// Get the transactions of a block, before the configured filters.
+///
+pub async fn get_block_transactions(
+    config: &Config,
+    rpc_pool: &RpcPool,
+    block: &Block,
+) -> IndexResult<Vec<tx::Response>> {
+    let height: i64 = block.header().height.into();
+    trace!("Fetching transactions for block {}", height);
 
-        found_txs += page_txs.len() as i64;
+    // Prefer rebuilding the transactions from the block itself, falling back to searching for them.
+    let txs = if config.transactions_from_block {
+        match get_transactions_from_block(rpc_pool, block).await {
+            Ok(txs) => txs,
+            Err(err) => {
+                trace!("Falling back to tx_search for block {}: {}", height, err);
+                search_transactions_for_block(config, rpc_pool, height).await?
+            }
+        }
+    } else {
+        search_transactions_for_block(config, rpc_pool, height).await?
+    };
 
-        txs.extend(page_txs);
+    // Index what the rpc knows about rather than failing the block forever.
+    let expected_txs = block.data().as_ref().len();
+    if let Some(missing) = transaction_shortfall(expected_txs, txs.len()) {
+        warn!(
+            "[{}] Found {} of {} transactions for block {}, {} were not returned by the rpc",
+            config.name,
+            txs.len(),
+            expected_txs,
+            height,
+            missing
+        );
     }
 
-    // Filter transactions based on the provided filters.
-    let txs = txs
-        .into_iter()
-        .filter(|tx| {
-            let mut matches = 0;
-            for filter in filters {
-                if filter.matches(tx) {
-                    matches += 1
-                }
-            }
-            matches == filters.len()
+    Ok(txs)
+}
+
+///
+/// Keep the transactions of a block passing the filters of a configuration, along with the gas used
+/// and wanted by all of them.
+///
+pub fn filter_block_transactions(
+    config: &Config,
+    filter_set: &CompiledFilterSet,
+    txs: Vec<tx::Response>,
+) -> (Vec<tx::Response>, (i64, i64)) {
+    // The gas totals are the block's, not only the ones of the transactions matching the filters.
+    let totals = gas_totals(&txs);
+
+    (filter_transactions(config, filter_set, txs), totals)
+}
+
+///
+/// Keep the transactions passing the hash lists, then the filters of a configuration.
+///
+pub fn filter_transactions(
+    config: &Config,
+    filter_set: &CompiledFilterSet,
+    txs: Vec<tx::Response>,
+) -> Vec<tx::Response> {
+    txs.into_iter()
+        .filter(|tx| config.matches_hash(&tx.hash.to_string()))
+        .filter(|tx| filter_set.matches(tx))
+        .collect()
+}
+
+///
+/// The number of transactions of a block missing from what was found, if any.
+///
+fn transaction_shortfall(expected: usize, found: usize) -> Option<usize> {
+    (found < expected).then_some(expected - found)
+}
+
+///
+/// Rebuild the transactions of a block from its data and `block_results`, saving the `tx_search` round-trips.
+///
+pub async fn get_transactions_from_block(
+    rpc_pool: &RpcPool,
+    block: &Block,
+) -> IndexResult<Vec<tx::Response>> {
+    let height = block.header().height;
+    let results = rpc_pool
+        .with_failover(|rpc_client| rpc::get_block_results(rpc_client, height.into()))
+        .await?;
+
+    let block_txs = block.data().as_ref();
+    let txs_results = results.txs_results.unwrap_or_default();
+    if block_txs.len() != txs_results.len() {
+        return Err(IndexError::invalid_data(format!(
+            "Block {} has {} transactions but {} results",
+            height,
+            block_txs.len(),
+            txs_results.len()
+        )));
+    }
+
+    Ok(block_txs
+        .iter()
+        .zip(txs_results)
+        .enumerate()
+        .map(|(index, (tx, tx_result))| tx::Response {
+            hash: transaction_hash(tx.as_bytes()),
+            height,
+            index: index as u32,
+            tx_result,
+            tx: tx.clone(),
+            proof: None,
         })
-        .collect::<Vec<_>>();
+        .collect())
+}
 
-    // Insert transactions into the database.
-    for tx in txs.iter() {
-        let transaction = TransactionModel::from_response(block.id, tx.clone())?;
-        transaction
-            .insert(db)
-            .await
-            .map_err(|e| eyre!("Failed to insert transaction: {}", e))?;
+///
+/// Compute the hash of a raw transaction the same way tendermint does.
+///
+pub fn transaction_hash(tx: &[u8]) -> abci::transaction::Hash {
+    abci::transaction::Hash::new(Sha256::digest(tx).into())
+}
+
+///
+/// Search for all the transactions of a block with `tx_search`.
+///
+pub async fn search_transactions_for_block(
+    config: &Config,
+    rpc_pool: &RpcPool,
+    height: i64,
+) -> IndexResult<Vec<tx::Response>> {
+    let request_timeout = Duration::from_secs(config.rpc_timeout_secs);
+
+    // Get a page of transactions for block from RPC, failing over to other clients on error.
+    collect_transaction_pages(height, |page| {
+        rpc::get_transactions_for_block_with_failover(
+            rpc_pool,
+            height,
+            page,
+            config.rpc_page_size,
+            request_timeout,
+        )
+    })
+    .await
+}
+
+///
+/// Fetch every page of the transaction search of a block.
+///
+/// The block data can hold transactions `tx_search` never returns (e.g. ones without results), so
+/// an empty page ends the search instead of failing the block. The transactions are returned by
+/// height and index within the block, whatever order the pages came back in.
+///
+async fn collect_transaction_pages<F, Fut>(
+    height: i64,
+    get_page: F,
+) -> IndexResult<Vec<tx::Response>>
+where
+    F: Fn(u32) -> Fut,
+    Fut: Future<Output = IndexResult<TransactionPage>>,
+{
+    // The first page tells us how many transactions there are in total.
+    let first_page = get_page(1).await?;
+    if first_page.txs.is_empty() {
+        warn!(
+            "No transactions found from RPC for block with transactions {}",
+            height
+        );
+        return Ok(vec![]);
+    }
+    let total_count = first_page.total_count;
+    let mut txs = first_page.txs;
+
+    // Size pages by what the rpc actually returned, in case it caps the page size.
+    let per_page = txs.len() as u32;
+    let total_pages = (total_count + per_page - 1) / per_page;
+
+    // Fetch the remaining pages concurrently, then put them back in order.
+    if total_pages > 1 {
+        let mut pages = stream::iter(2..=total_pages)
+            .map(|page| {
+                let page_txs = get_page(page);
+                async move { Ok::<_, IndexError>((page, page_txs.await?)) }
+            })
+            .buffer_unordered(MAX_CONCURRENT_PAGE_REQUESTS)
+            .try_collect::<Vec<_>>()
+            .await?;
+        pages.sort_by_key(|(page, ..)| *page);
+
+        for (page, page_txs) in pages {
+            if page_txs.txs.is_empty() {
+                warn!(
+                    "Found {} of {} transactions for block {}, page {} is empty",
+                    txs.len(),
+                    total_count,
+                    height,
+                    page
+                );
+                break;
+            }
+            txs.extend(page_txs.txs);
+        }
+    }
+
+    // Providers don't all honor the ascending order across pages, so don't rely on it.
+    txs.sort_by_key(|tx| (tx.height, tx.index));
+
+    Ok(txs)
+}
+
+///
+/// The total gas used and wanted by a set of transactions.
+///
+pub fn gas_totals(txs: &[tx::Response]) -> (i64, i64) {
+    txs.iter().fold((0, 0), |(used, wanted), tx| {
+        (
+            used + tx.tx_result.gas_used.value() as i64,
+            wanted + tx.tx_result.gas_wanted.value() as i64,
+        )
+    })
+}
+
+///
+/// Insert the transactions of a block into the database, returning the ones that were inserted.
+///
+/// The `transfer`, `message` and `wasm` events of each inserted transaction are decoded into the
+/// `transaction_event` table. Without `store_events_json`, those rows are all that's kept of the
+/// events.
+///
+pub async fn index_transactions_for_block<C>(
+    db: &C,
+    block: &DatabaseBlock,
+    txs: Vec<tx::Response>,
+    account_prefix: Option<&str>,
+    store_events_json: bool,
+) -> IndexResult<Vec<tx::Response>>
+where
+    C: ConnectionTrait,
+{
+    let found_txs = txs.len();
+    let mut inserted_txs = vec![];
+
+    // Insert transactions into the database, ignoring ones that were already indexed.
+    for tx in txs {
+        let mut transaction = TransactionModel::from_response(
+            block.id,
+            tx.clone(),
+            account_prefix,
+            store_events_json,
+        )?;
+        // SQLite keeps the column NOT NULL, so an empty array stands in for the events there.
+        if !store_events_json && db.get_database_backend() == DbBackend::Sqlite {
+            transaction.events = Set(Some(serde_json::json!([])));
+        }
+        let transaction_id = transaction.id.clone().unwrap();
+        let inserted = TransactionEntity::insert(transaction)
+            .on_conflict(
+                OnConflict::columns([TransactionColumn::Hash, TransactionColumn::Height])
+                    .do_nothing()
+                    .to_owned(),
+            )
+            .exec_without_returning(db)
+            .await?;
+        if inserted > 0 {
+            let events = events::transaction_event_models(
+                transaction_id,
+                block.height,
+                &tx.tx_result.events,
+            );
+            if !events.is_empty() {
+                TransactionEventEntity::insert_many(events)
+                    .exec_without_returning(db)
+                    .await?;
+            }
+            inserted_txs.push(tx);
+        }
     }
 
     trace!(
-        "Successfully inserted {} transactions for height {}",
+        "Successfully inserted {} of {} transactions for height {}",
+        inserted_txs.len(),
         found_txs,
         block.height
     );
 
-    Ok(())
+    Ok(inserted_txs)
 }
 
 ///
-/// Index historical blocks into the database.
+/// The outcome of a historical indexing pass.
+///
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HistoricalPass {
+    /// The number of gaps found.
+    pub gaps: usize,
+    /// The number of blocks indexed to close them.
+    pub indexed_blocks: u64,
+}
+
+///
+/// Index historical blocks into the database, returning the gaps found and blocks indexed.
+///
+/// The gaps are split into chunks recorded as backfill jobs, which are indexed least attempted
+/// first so a range that keeps failing doesn't hold back the others.
 ///
 pub async fn index_historical_blocks(
-    name: &str,
-    chain_id: &str,
-    rpc_client: &HttpClient,
+    config: &Config,
+    rpc_pool: &RpcPool,
     db: &DatabaseConnection,
-    filters: &[Filter],
-) -> Result<()> {
-    let gaps = get_block_gaps(db, chain_id.to_string(), 7).await?;
+    sinks: &Sinks,
+    filter_set: &CompiledFilterSet,
+    shutdown: &CancellationToken,
+) -> IndexResult<HistoricalPass> {
+    let Config { name, chain_id, .. } = config;
+    let mut gaps = get_block_gaps(
+        db,
+        chain_id.to_string(),
+        GAP_LOOKBACK_DAYS,
+        config.start_height,
+    )
+    .await?;
+    if let Some(stop_height) = config.stop_height {
+        clip_gaps(&mut gaps, stop_height);
+    }
+    let found_gaps = gaps.len();
+
+    // Fill the large recent gaps first, they matter more than the blocks lost to a reorg.
+    let now = chrono::Utc::now().naive_utc();
+    prioritize_gaps(&mut gaps, now);
+    let severe_gaps = gaps
+        .iter()
+        .filter(|gap| gap.severity(now) == GapSeverity::Severe)
+        .count();
+
+    // Closing the jobs of gaps filled since the last pass, so the queue never goes stale.
+    let ranges = gaps
+        .iter()
+        .flat_map(|gap| gap.chunks(config.historical_chunk_size))
+        .collect::<Vec<_>>();
+    if !config.dry_run {
+        backfill::sync_backfill_jobs(db, chain_id, &ranges).await?;
+    }
 
     if gaps.is_empty() {
         info!("No gaps found, skipping historical block indexing");
-        return Ok(());
+        return Ok(HistoricalPass::default());
     }
 
+    let missing_heights: u64 = gaps.iter().map(|gap| gap.num_heights()).sum();
+    let mut indexed_heights = 0;
     info!(
-        "Found {} gaps in block history for {} ({})",
-        name,
-        chain_id,
-        gaps.len()
+        "[{}] Found {} gaps ({} severe) with {} missing blocks in block history for {}",
+        name, found_gaps, severe_gaps, missing_heights, chain_id
     );
 
-    for gap in gaps {
-        for range in gap {
-            let (start, end) = *range;
-            info!("Indexing gap blocks from {} to {}", start, end);
-            let block = rpc::get_block(rpc_client, start).await?;
-            index_block(db, rpc_client, filters, block.into()).await?;
+    // A dry run doesn't fill the gaps, so it doesn't record jobs either.
+    let jobs = if config.dry_run {
+        ranges.into_iter().map(|range| (range, None)).collect()
+    } else {
+        // Still the least attempted jobs first, then those of the most pressing gaps.
+        let mut jobs = backfill::get_pending_backfill_jobs(db, chain_id).await?;
+        jobs.sort_by_key(|job| (job.attempts, gap_rank(&gaps, &job.range())));
+        jobs.into_iter()
+            .map(|job| (job.range(), Some(job)))
+            .collect::<Vec<_>>()
+    };
+
+    for (range, job) in jobs {
+        let (start, end) = *range;
+        let job = match job {
+            Some(job) => {
+                if job.attempts > 0 {
+                    warn!(
+                        "[{}] Retrying gap blocks from {} to {} after {} attempts: {}",
+                        name,
+                        start,
+                        end,
+                        job.attempts,
+                        job.last_error.as_deref().unwrap_or("interrupted")
+                    );
+                }
+                Some(backfill::start_backfill_job(db, job).await?)
+            }
+            None => None,
+        };
+
+        info!("Indexing gap blocks from {} to {}", start, end);
+        let result = index_gap_range(
+            config,
+            rpc_pool,
+            db,
+            sinks,
+            filter_set,
+            shutdown,
+            range,
+            &mut indexed_heights,
+        )
+        .await;
+
+        match (result, job) {
+            (Ok(true), Some(job)) => {
+                backfill::finish_backfill_job(db, job).await?;
+            }
+            (Ok(false), Some(job)) => {
+                backfill::release_backfill_job(db, job).await?;
+            }
+            (Err(err), Some(job)) => {
+                backfill::fail_backfill_job(db, job, err.to_string()).await?;
+                return Err(err);
+            }
+            (Err(err), None) => return Err(err),
+            (Ok(_), None) => {}
+        }
+
+        // Stop between blocks so we never leave one half indexed.
+        if shutdown.is_cancelled() {
+            return Ok(HistoricalPass {
+                gaps: found_gaps,
+                indexed_blocks: indexed_heights,
+            });
+        }
+
+        trace!("Finished indexing gap chunk {} to {}", start, end);
+        info!(
+            "[{}] Indexed {} of {} missing blocks, {:.1}%",
+            name,
+            indexed_heights,
+            missing_heights,
+            percentage(indexed_heights, missing_heights)
+        );
+    }
+
+    Ok(HistoricalPass {
+        gaps: found_gaps,
+        indexed_blocks: indexed_heights,
+    })
+}
+
+///
+/// Index the blocks of a gap range one by one, returning `false` when interrupted by a shutdown.
+///
+/// Each block is committed as it is indexed, so a restart picks up from the next gap query.
+///
+#[allow(clippy::too_many_arguments)]
+async fn index_gap_range(
+    config: &Config,
+    rpc_pool: &RpcPool,
+    db: &DatabaseConnection,
+    sinks: &Sinks,
+    filter_set: &CompiledFilterSet,
+    shutdown: &CancellationToken,
+    range: BlockRange,
+    indexed_heights: &mut u64,
+) -> IndexResult<bool> {
+    let (start, end) = *range;
+    for height in start..=end {
+        if shutdown.is_cancelled() {
+            return Ok(false);
         }
+
+        let block = rpc_pool
+            .with_failover(|rpc_client| rpc::get_block(rpc_client, height))
+            .await?;
+        index_block(config, db, rpc_pool, sinks, filter_set, block.into()).await?;
+        *indexed_heights += 1;
+    }
+
+    Ok(true)
+}
+
+///
+/// Index the blocks produced since the highest indexed height, up to the current chain height.
+///
+/// At most [`MAX_CATCH_UP_BLOCKS`] are caught up, older heights are left to the historical indexer
+/// as a gap.
+///
+pub async fn catch_up_blocks(
+    config: &Config,
+    db: &DatabaseConnection,
+    rpc_pool: &RpcPool,
+    sinks: &Sinks,
+    filter_set: &CompiledFilterSet,
+    shutdown: &CancellationToken,
+) -> IndexResult<()> {
+    let Config { name, chain_id, .. } = config;
+    let max_indexed_height = match get_max_indexed_height(db, chain_id).await? {
+        Some(height) => height,
+        None => {
+            trace!(
+                "[{}] Nothing indexed for {} yet, skipping catch up",
+                name,
+                chain_id
+            );
+            return Ok(());
+        }
+    };
+
+    // Keep going until the chain stops moving ahead of us.
+    let mut next_height = max_indexed_height + 1;
+    loop {
+        let latest_height: i64 = rpc_pool
+            .with_failover(rpc::get_latest_block)
+            .await?
+            .header
+            .height
+            .into();
+        let latest_height = latest_height.min(config.stop_height.unwrap_or(i64::MAX));
+        if next_height > latest_height {
+            break;
+        }
+
+        let from = next_height
+            .max(latest_height - MAX_CATCH_UP_BLOCKS + 1)
+            .max(config.start_height.unwrap_or_default());
+        info!(
+            "[{}] Catching up blocks {} to {} on {}",
+            name, from, latest_height, chain_id
+        );
+        index_block_range(
+            config,
+            db,
+            rpc_pool,
+            sinks,
+            filter_set,
+            (from, latest_height).into(),
+            shutdown,
+        )
+        .await?;
+        if shutdown.is_cancelled() {
+            return Ok(());
+        }
+        next_height = latest_height + 1;
+    }
+
+    Ok(())
+}
+
+///
+/// Fetch and index every block of an inclusive height range, stopping between blocks on shutdown.
+///
+pub async fn index_block_range(
+    config: &Config,
+    db: &DatabaseConnection,
+    rpc_pool: &RpcPool,
+    sinks: &Sinks,
+    filter_set: &CompiledFilterSet,
+    range: BlockRange,
+    shutdown: &CancellationToken,
+) -> IndexResult<()> {
+    let (start, end) = *range;
+    for height in start..=end {
+        if shutdown.is_cancelled() {
+            return Ok(());
+        }
+
+        let block = rpc_pool
+            .with_failover(|rpc_client| rpc::get_block(rpc_client, height))
+            .await?;
+        index_block(config, db, rpc_pool, sinks, filter_set, block.into()).await?;
     }
 
     Ok(())
 }
+
+///
+/// The percentage of `total` that `done` represents.
+///
+fn percentage(done: u64, total: u64) -> f64 {
+    if total == 0 {
+        return 100.0;
+    }
+
+    done as f64 / total as f64 * 100.0
+}
+
+///
+/// Select the ids of the indexed blocks of a chain within a height range.
+///
+fn block_ids_in_range(chain_id: &str, from: i64, to: i64) -> SelectStatement {
+    Query::select()
+        .column(BlockColumn::Id)
+        .from(BlockEntity)
+        .and_where(BlockColumn::ChainId.eq(chain_id))
+        .and_where(BlockColumn::Height.between(from, to))
+        .to_owned()
+}
+
+///
+/// Delete and index again the blocks of a chain within an inclusive height range.
+///
+/// With `dry_run` set, only report what would be reindexed.
+///
+#[allow(clippy::too_many_arguments)]
+pub async fn reindex_blocks(
+    config: &Config,
+    db: &DatabaseConnection,
+    rpc_pool: &RpcPool,
+    sinks: &Sinks,
+    filter_set: &CompiledFilterSet,
+    from: i64,
+    to: i64,
+    dry_run: bool,
+) -> IndexResult<()> {
+    let Config { name, chain_id, .. } = config;
+    if from > to {
+        return Err(IndexError::invalid_data(format!(
+            "Invalid height range {} to {}",
+            from, to
+        )));
+    }
+    // The deleted blocks would never be written back.
+    if matches!(config.start_height, Some(start) if from < start) {
+        return Err(IndexError::invalid_data(format!(
+            "Height {} is below the start height {} of {}",
+            from,
+            config.start_height.unwrap_or_default(),
+            name
+        )));
+    }
+    if matches!(config.stop_height, Some(stop) if to > stop) {
+        return Err(IndexError::invalid_data(format!(
+            "Height {} is above the stop height {} of {}",
+            to,
+            config.stop_height.unwrap_or_default(),
+            name
+        )));
+    }
+
+    if dry_run {
+        let blocks = BlockEntity::find()
+            .filter(BlockColumn::ChainId.eq(chain_id.as_str()))
+            .filter(BlockColumn::Height.between(from, to))
+            .count(db)
+            .await?;
+        let transactions = TransactionEntity::find()
+            .filter(TransactionColumn::BlockId.in_subquery(block_ids_in_range(chain_id, from, to)))
+            .count(db)
+            .await?;
+        info!(
+            "[{}] Would reindex {} heights from {} to {} on {}, replacing {} blocks and {} transactions",
+            name,
+            to - from + 1,
+            from,
+            to,
+            chain_id,
+            blocks,
+            transactions
+        );
+        return Ok(());
+    }
+
+    // Remove the existing rows together, transactions first as they reference their block.
+    let db_transaction = db.begin().await?;
+    let transactions = TransactionEntity::delete_many()
+        .filter(TransactionColumn::BlockId.in_subquery(block_ids_in_range(chain_id, from, to)))
+        .exec(&db_transaction)
+        .await?;
+    let blocks = BlockEntity::delete_many()
+        .filter(BlockColumn::ChainId.eq(chain_id.as_str()))
+        .filter(BlockColumn::Height.between(from, to))
+        .exec(&db_transaction)
+        .await?;
+    SkippedBlockEntity::delete_many()
+        .filter(SkippedBlockColumn::ChainId.eq(chain_id.as_str()))
+        .filter(SkippedBlockColumn::Height.between(from, to))
+        .exec(&db_transaction)
+        .await?;
+    db_transaction.commit().await?;
+
+    info!(
+        "[{}] Deleted {} blocks and {} transactions from {} to {} on {}, reindexing",
+        name, blocks.rows_affected, transactions.rows_affected, from, to, chain_id
+    );
+
+    for height in from..=to {
+        let block = rpc_pool
+            .with_failover(|rpc_client| rpc::get_block(rpc_client, height))
+            .await?;
+        index_block(config, db, rpc_pool, sinks, filter_set, block.into()).await?;
+    }
+
+    info!(
+        "[{}] Reindexed blocks {} to {} on {}",
+        name, from, to, chain_id
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use sea_orm::QueryOrder;
+
+    use super::*;
+    use crate::indexer::model::transaction_event::Column as TransactionEventColumn;
+    use crate::streams::fixture::{block_from_json, block_json, test_block};
+
+    #[test]
+    fn parse_block_time_edge_cases() {
+        let expected = NaiveDate::from_ymd(2022, 10, 12).and_hms_nano(14, 16, 5, 123_456_789);
+        assert_eq!(
+            parse_block_time("2022-10-12T14:16:05.123456789Z").unwrap(),
+            expected
+        );
+        // Offsets are normalized to UTC.
+        assert_eq!(
+            parse_block_time("2022-10-12T16:16:05.123456789+02:00").unwrap(),
+            expected
+        );
+        // No fractional seconds.
+        assert_eq!(
+            parse_block_time("2022-10-12T14:16:05Z").unwrap(),
+            NaiveDate::from_ymd(2022, 10, 12).and_hms(14, 16, 5)
+        );
+        // Leap seconds.
+        assert!(parse_block_time("2016-12-31T23:59:60Z").is_ok());
+
+        assert!(parse_block_time("2022-10-12 14:16:05").is_err());
+    }
+
+    pub(super) fn event(type_str: &str, attributes: &[(&str, &str)]) -> abci::Event {
+        abci::Event {
+            type_str: type_str.to_string(),
+            attributes: attributes
+                .iter()
+                .map(|(key, value)| abci::tag::Tag {
+                    key: key.parse().unwrap(),
+                    value: value.parse().unwrap(),
+                })
+                .collect(),
+        }
+    }
+
+    pub(super) fn transaction_response(hash: &str, height: u64) -> tx::Response {
+        serde_json::from_value(serde_json::json!({
+            "hash": hash,
+            "height": height.to_string(),
+            "index": 0,
+            "tx_result": {
+                "code": 0,
+                "data": null,
+                "log": "",
+                "info": "",
+                "gas_wanted": "0",
+                "gas_used": "0",
+                "events": [],
+                "codespace": ""
+            },
+            "tx": "Y3JvbmNhdA==",
+        }))
+        .unwrap()
+    }
+
+    fn gas_transaction_response(hash: &str, gas_used: u64, gas_wanted: u64) -> tx::Response {
+        let mut tx = transaction_response(hash, 1);
+        tx.tx_result.gas_used = gas_used.into();
+        tx.tx_result.gas_wanted = gas_wanted.into();
+        tx
+    }
+
+    #[test]
+    fn filter_transactions_by_hash_and_events() {
+        let mut config: Config = serde_yaml::from_str(indoc::indoc! {r#"
+            name: test
+            chain_id: uni-5
+            sources: []
+            filters:
+              - type: wasm
+                attributes:
+                  - key: _contract_address
+                    value: ^juno1croncat$
+        "#})
+        .unwrap();
+        let tx = |hash: &str, contract: &str| {
+            let mut tx = transaction_response(&hash.repeat(64), 1);
+            tx.tx_result.events = vec![event("wasm", &[("_contract_address", contract)])];
+            tx
+        };
+        let txs = vec![
+            tx("A", "juno1croncat"),
+            tx("B", "juno1croncat"),
+            tx("C", "juno1other"),
+        ];
+        let hashes = |txs: Vec<tx::Response>| {
+            txs.iter()
+                .map(|tx| tx.hash.to_string()[..1].to_string())
+                .collect::<Vec<_>>()
+        };
+        let filter_set = CompiledFilterSet::from_config(&config).unwrap();
+
+        assert_eq!(
+            hashes(filter_transactions(&config, &filter_set, txs.clone())),
+            ["A", "B"]
+        );
+
+        // Both the hash lists and the event filters must pass.
+        config.include_hashes = vec!["b".repeat(64), "c".repeat(64)];
+        assert_eq!(
+            hashes(filter_transactions(&config, &filter_set, txs.clone())),
+            ["B"]
+        );
+
+        config.include_hashes = vec![];
+        config.exclude_hashes = vec!["a".repeat(64)];
+        assert_eq!(
+            hashes(filter_transactions(&config, &filter_set, txs)),
+            ["B"]
+        );
+    }
+
+    #[test]
+    fn filter_block_transactions_counts_filtered_gas() {
+        let mut config: Config = serde_yaml::from_str(indoc::indoc! {r#"
+            name: test
+            chain_id: uni-5
+            sources: []
+            filters: []
+        "#})
+        .unwrap();
+        config.exclude_hashes = vec!["a".repeat(64)];
+        let filter_set = CompiledFilterSet::from_config(&config).unwrap();
+
+        let (txs, totals) = filter_block_transactions(
+            &config,
+            &filter_set,
+            vec![
+                gas_transaction_response(&"A".repeat(64), 80_000, 100_000),
+                gas_transaction_response(&"B".repeat(64), 150_000, 200_000),
+            ],
+        );
+
+        // The excluded transaction isn't kept, but its gas counts towards the block's.
+        assert_eq!(txs.len(), 1);
+        assert_eq!(txs[0].hash.to_string(), "B".repeat(64));
+        assert_eq!(totals, (230_000, 300_000));
+    }
+
+    fn transaction_page(hashes: &[&str], total_count: u32) -> TransactionPage {
+        TransactionPage {
+            txs: hashes
+                .iter()
+                .map(|hash| transaction_response(&hash.repeat(64), 1))
+                .collect(),
+            total_count,
+        }
+    }
+
+    #[tokio::test]
+    async fn collect_transaction_pages_in_order() {
+        let txs = collect_transaction_pages(1, |page| async move {
+            Ok(match page {
+                1 => transaction_page(&["A", "B"], 5),
+                2 => transaction_page(&["C", "D"], 5),
+                _ => transaction_page(&["E"], 5),
+            })
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(
+            txs.iter().map(|tx| tx.hash.to_string()).collect::<Vec<_>>(),
+            ["A", "B", "C", "D", "E"]
+                .iter()
+                .map(|hash| hash.repeat(64))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[tokio::test]
+    async fn collect_transaction_pages_sorts_shuffled_pages() {
+        // The provider serves the second half of the block first, out of order within pages too.
+        let page = |hashes: &[(&str, u32)]| TransactionPage {
+            txs: hashes
+                .iter()
+                .map(|(hash, index)| {
+                    let mut tx = transaction_response(&hash.repeat(64), 1);
+                    tx.index = *index;
+                    tx
+                })
+                .collect(),
+            total_count: 5,
+        };
+        let txs = collect_transaction_pages(1, |number| {
+            let txs = match number {
+                1 => page(&[("D", 3), ("C", 2)]),
+                2 => page(&[("A", 0), ("E", 4)]),
+                _ => page(&[("B", 1)]),
+            };
+            async move { Ok(txs) }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(
+            txs.iter().map(|tx| tx.index).collect::<Vec<_>>(),
+            [0, 1, 2, 3, 4]
+        );
+        assert_eq!(
+            txs.iter().map(|tx| tx.hash.to_string()).collect::<Vec<_>>(),
+            ["A", "B", "C", "D", "E"]
+                .iter()
+                .map(|hash| hash.repeat(64))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[tokio::test]
+    async fn collect_transaction_pages_without_results() {
+        // The block data holds transactions, but the search finds none of them.
+        let requested = std::sync::Mutex::new(vec![]);
+        let txs = collect_transaction_pages(1, |page| {
+            requested.lock().unwrap().push(page);
+            async move { Ok(transaction_page(&[], 0)) }
+        })
+        .await
+        .unwrap();
+
+        assert!(txs.is_empty());
+        assert_eq!(*requested.lock().unwrap(), vec![1]);
+    }
+
+    #[tokio::test]
+    async fn collect_transaction_pages_capped_by_total_count() {
+        // The block data holds 5 transactions, but the node only indexed 3 of them.
+        let requested = std::sync::Mutex::new(vec![]);
+        let txs = collect_transaction_pages(1, |page| {
+            requested.lock().unwrap().push(page);
+            async move {
+                Ok(match page {
+                    1 => transaction_page(&["A", "B"], 3),
+                    2 => transaction_page(&["C"], 3),
+                    _ => panic!("requested page {} past the total count", page),
+                })
+            }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(txs.len(), 3);
+        assert_eq!(*requested.lock().unwrap(), vec![1, 2]);
+        assert_eq!(transaction_shortfall(5, txs.len()), Some(2));
+        assert_eq!(transaction_shortfall(3, txs.len()), None);
+    }
+
+    #[tokio::test]
+    async fn collect_transaction_pages_stops_at_empty_page() {
+        let txs = collect_transaction_pages(1, |page| async move {
+            Ok(match page {
+                1 => transaction_page(&["A", "B"], 6),
+                2 => transaction_page(&[], 6),
+                _ => transaction_page(&["E"], 6),
+            })
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(txs.len(), 2);
+    }
+
+    #[test]
+    fn block_model_records_source() {
+        let model = BlockModel::try_from(test_block(1)).unwrap();
+        assert_eq!(model.height, Set(1));
+        assert_eq!(model.source, Set(None));
+
+        let model =
+            BlockModel::try_from(test_block(1).with_source("rpc.uni.junonetwork.io")).unwrap();
+        assert_eq!(
+            model.source,
+            Set(Some("rpc.uni.junonetwork.io".to_string()))
+        );
+    }
+
+    ///
+    /// A block whose last commit was signed by one validator, voted nil by another and missed by a
+    /// third.
+    ///
+    fn block_with_last_commit() -> Block {
+        let signature = base64::encode([0u8; 64]);
+        let mut json = block_json(2);
+        json["last_commit"]["signatures"] = serde_json::json!([
+            {
+                "block_id_flag": 2,
+                "validator_address": "1111111111111111111111111111111111111111",
+                "timestamp": "2022-10-12T14:16:04.5Z",
+                "signature": signature
+            },
+            {
+                "block_id_flag": 3,
+                "validator_address": "2222222222222222222222222222222222222222",
+                "timestamp": "2022-10-12T14:16:04.75Z",
+                "signature": signature
+            },
+            {
+                "block_id_flag": 1,
+                "validator_address": "",
+                "timestamp": "0001-01-01T00:00:00Z",
+                "signature": null
+            }
+        ]);
+        block_from_json(json)
+    }
+
+    #[test]
+    fn block_model_counts_last_commit_signatures() {
+        let model = BlockModel::try_from(test_block(1)).unwrap();
+        assert_eq!(model.last_commit_signatures, Set(None));
+        assert_eq!(model.last_commit_validators, Set(None));
+
+        let model = BlockModel::try_from(block_with_last_commit()).unwrap();
+        assert_eq!(model.last_commit_signatures, Set(Some(1)));
+        assert_eq!(model.last_commit_validators, Set(Some(3)));
+    }
+
+    #[test]
+    fn block_signature_models_of_last_commit() {
+        assert!(block_signature_models(&test_block(1)).unwrap().is_empty());
+
+        // The absent validator has no address to store.
+        let signatures = block_signature_models(&block_with_last_commit()).unwrap();
+        assert_eq!(signatures.len(), 2);
+        assert_eq!(signatures[0].height, Set(1));
+        assert_eq!(signatures[0].chain_id, Set("uni-5".to_string()));
+        assert_eq!(
+            signatures[0].validator_address,
+            Set("1111111111111111111111111111111111111111".to_string())
+        );
+        assert_eq!(signatures[0].block_id_flag, Set("commit".to_string()));
+        assert_eq!(
+            signatures[0].time,
+            Set(NaiveDate::from_ymd(2022, 10, 12).and_hms_milli(14, 16, 4, 500))
+        );
+        assert_eq!(signatures[1].block_id_flag, Set("nil".to_string()));
+    }
+
+    #[test]
+    fn gas_totals_sum_transactions() {
+        assert_eq!(gas_totals(&[]), (0, 0));
+        assert_eq!(
+            gas_totals(&[
+                gas_transaction_response(&"A".repeat(64), 80_000, 100_000),
+                gas_transaction_response(&"B".repeat(64), 150_000, 200_000),
+            ]),
+            (230_000, 300_000)
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a migrated database at DATABASE_URL"]
+    async fn block_gas_totals_are_stored() {
+        let db = Database::connect(std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        let db_transaction = db.begin().await.unwrap();
+        let mut config: Config = serde_yaml::from_str(indoc::indoc! {r#"
+            name: test
+            chain_id: croncat-test-1
+            sources: []
+            filters: []
+        "#})
+        .unwrap();
+        config.exclude_hashes = vec!["a".repeat(64)];
+        let filter_set = CompiledFilterSet::from_config(&config).unwrap();
+
+        let (txs, (total_gas_used, total_gas_wanted)) = filter_block_transactions(
+            &config,
+            &filter_set,
+            vec![
+                gas_transaction_response(&"A".repeat(64), 80_000, 100_000),
+                gas_transaction_response(&"B".repeat(64), 150_000, 200_000),
+            ],
+        );
+        let mut gas_block = block_model(Uuid::new_v4(), &"0".repeat(64));
+        gas_block.total_gas_used = Set(total_gas_used);
+        gas_block.total_gas_wanted = Set(total_gas_wanted);
+        save_block(&config, &db_transaction, gas_block, txs)
+            .await
+            .unwrap();
+
+        // Only the matching transaction is stored, the totals still cover both.
+        let stored = BlockEntity::find_by_id((1, "croncat-test-1".to_string()))
+            .one(&db_transaction)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.total_gas_used, 230_000);
+        assert_eq!(stored.total_gas_wanted, 300_000);
+        let transactions = TransactionEntity::find()
+            .filter(TransactionColumn::BlockId.eq(stored.id))
+            .all(&db_transaction)
+            .await
+            .unwrap();
+        assert_eq!(transactions.len(), 1);
+
+        db_transaction.rollback().await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a migrated database at DATABASE_URL"]
+    async fn empty_block_gas_totals_are_zero() {
+        let db = Database::connect(std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        let db_transaction = db.begin().await.unwrap();
+
+        let empty_block = block_model(Uuid::new_v4(), &"0".repeat(64))
+            .insert(&db_transaction)
+            .await
+            .unwrap();
+        index_transactions_for_block(&db_transaction, &empty_block, vec![], None, true)
+            .await
+            .unwrap();
+
+        let stored = BlockEntity::find_by_id((1, "croncat-test-1".to_string()))
+            .one(&db_transaction)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!((stored.total_gas_used, stored.total_gas_wanted), (0, 0));
+
+        db_transaction.rollback().await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a migrated database at DATABASE_URL"]
+    async fn reindexing_transactions_is_idempotent() {
+        let db = Database::connect(std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        let db_transaction = db.begin().await.unwrap();
+
+        let block = BlockModel {
+            id: Set(Uuid::new_v4()),
+            height: Set(1),
+            chain_id: Set("croncat-test-1".to_string()),
+            time: Set(NaiveDate::from_ymd(2022, 10, 12).and_hms(14, 16, 5)),
+            hash: Set("0".repeat(64)),
+            num_txs: Set(2),
+            proposer_address: Set(None),
+            app_hash: Set(None),
+            last_commit_hash: Set(None),
+            total_gas_used: Set(0),
+            total_gas_wanted: Set(0),
+            source: Set(None),
+            last_commit_signatures: Set(None),
+            last_commit_validators: Set(None),
+        }
+        .insert(&db_transaction)
+        .await
+        .unwrap();
+        let txs = || {
+            vec![
+                transaction_response(&"A".repeat(64), 1),
+                transaction_response(&"B".repeat(64), 1),
+            ]
+        };
+
+        // Only the first pass inserts anything.
+        for expected in [2, 0] {
+            let inserted = index_transactions_for_block(&db_transaction, &block, txs(), None, true)
+                .await
+                .unwrap();
+            assert_eq!(inserted.len(), expected);
+        }
+
+        let count = TransactionEntity::find()
+            .filter(TransactionColumn::BlockId.eq(block.id))
+            .count(&db_transaction)
+            .await
+            .unwrap();
+        assert_eq!(count, 2);
+
+        db_transaction.rollback().await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a migrated database at DATABASE_URL"]
+    async fn transaction_events_are_decoded() {
+        let db = Database::connect(std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        let db_transaction = db.begin().await.unwrap();
+
+        let block = block_model(Uuid::new_v4(), &"0".repeat(64))
+            .insert(&db_transaction)
+            .await
+            .unwrap();
+        let mut tx = transaction_response(&"A".repeat(64), 1);
+        tx.tx_result.events = vec![
+            event("tx", &[("fee", "4000ujunox")]),
+            event(
+                "transfer",
+                &[
+                    ("recipient", "juno1recipient"),
+                    ("sender", "juno1sender"),
+                    ("amount", "4000ujunox"),
+                ],
+            ),
+        ];
+        index_transactions_for_block(&db_transaction, &block, vec![tx], None, true)
+            .await
+            .unwrap();
+
+        let events = TransactionEventEntity::find()
+            .inner_join(TransactionEntity)
+            .filter(TransactionColumn::BlockId.eq(block.id))
+            .all(&db_transaction)
+            .await
+            .unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event_index, 1);
+        assert_eq!(events[0].event_type, "transfer");
+        assert_eq!(events[0].sender.as_deref(), Some("juno1sender"));
+        assert_eq!(events[0].recipient.as_deref(), Some("juno1recipient"));
+        assert_eq!(events[0].amount.as_deref(), Some("4000ujunox"));
+        assert_eq!(events[0].contract_address, None);
+
+        db_transaction.rollback().await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a migrated database at DATABASE_URL"]
+    async fn transaction_events_json_is_optional() {
+        let db = Database::connect(std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        let db_transaction = db.begin().await.unwrap();
+
+        let block = block_model(Uuid::new_v4(), &"0".repeat(64))
+            .insert(&db_transaction)
+            .await
+            .unwrap();
+        for (hash, store_events_json) in [("A".repeat(64), true), ("B".repeat(64), false)] {
+            let mut tx = transaction_response(&hash, 1);
+            tx.tx_result.events = vec![event("message", &[("sender", "juno1sender")])];
+            index_transactions_for_block(
+                &db_transaction,
+                &block,
+                vec![tx],
+                None,
+                store_events_json,
+            )
+            .await
+            .unwrap();
+        }
+
+        let transactions = TransactionEntity::find()
+            .filter(TransactionColumn::BlockId.eq(block.id))
+            .order_by_asc(TransactionColumn::Hash)
+            .all(&db_transaction)
+            .await
+            .unwrap();
+        assert_eq!(
+            transactions[0].events,
+            Some(serde_json::json!([{
+                "type": "message",
+                "attributes": [{"key": "sender", "value": "juno1sender"}],
+            }]))
+        );
+        assert!(matches!(
+            transactions[1].events.as_ref(),
+            None | Some(serde_json::Value::Array(events)) if events.is_empty()
+        ));
+
+        // The decoded events are stored either way.
+        for transaction in transactions {
+            let count = TransactionEventEntity::find()
+                .filter(TransactionEventColumn::TransactionId.eq(transaction.id))
+                .count(&db_transaction)
+                .await
+                .unwrap();
+            assert_eq!(count, 1);
+        }
+
+        db_transaction.rollback().await.unwrap();
+    }
+
+    fn block_model(id: Uuid, hash: &str) -> BlockModel {
+        BlockModel {
+            id: Set(id),
+            height: Set(1),
+            chain_id: Set("croncat-test-1".to_string()),
+            time: Set(NaiveDate::from_ymd(2022, 10, 12).and_hms(14, 16, 5)),
+            hash: Set(hash.to_string()),
+            num_txs: Set(1),
+            proposer_address: Set(None),
+            app_hash: Set(None),
+            last_commit_hash: Set(None),
+            total_gas_used: Set(0),
+            total_gas_wanted: Set(0),
+            source: Set(None),
+            last_commit_signatures: Set(None),
+            last_commit_validators: Set(None),
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a migrated database at DATABASE_URL"]
+    async fn reorged_block_is_replaced() {
+        let db = Database::connect(std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        let db_transaction = db.begin().await.unwrap();
+        let config: Config = serde_yaml::from_str(indoc::indoc! {r#"
+            name: test
+            chain_id: croncat-test-1
+            sources: []
+            filters: []
+            handle-reorgs: true
+        "#})
+        .unwrap();
+
+        let id = Uuid::new_v4();
+        let tx_hash = "C".repeat(64);
+        let reorged_tx_hash = "D".repeat(64);
+        save_block(
+            &config,
+            &db_transaction,
+            block_model(id, &"A".repeat(64)),
+            vec![transaction_response(&tx_hash, 1)],
+        )
+        .await
+        .unwrap();
+
+        // The same height arrives again with a different hash, only the first one replaces it.
+        for replaced in [true, false] {
+            let saved = save_block(
+                &config,
+                &db_transaction,
+                block_model(Uuid::new_v4(), &"B".repeat(64)),
+                vec![transaction_response(&reorged_tx_hash, 1)],
+            )
+            .await
+            .unwrap();
+            assert_eq!(saved.is_some(), replaced);
+        }
+
+        let block = BlockEntity::find_by_id((1, "croncat-test-1".to_string()))
+            .one(&db_transaction)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(block.id, id);
+        assert_eq!(block.hash, "B".repeat(64));
+
+        let transactions = TransactionEntity::find()
+            .filter(TransactionColumn::BlockId.eq(id))
+            .all(&db_transaction)
+            .await
+            .unwrap();
+        assert_eq!(transactions.len(), 1);
+        assert_eq!(transactions[0].hash, reorged_tx_hash);
+
+        db_transaction.rollback().await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a migrated database at DATABASE_URL"]
+    async fn conflicting_block_follows_on_conflict() {
+        let db = Database::connect(std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+
+        for on_conflict in [
+            ConflictMode::Skip,
+            ConflictMode::Overwrite,
+            ConflictMode::Fail,
+        ] {
+            let db_transaction = db.begin().await.unwrap();
+            let mut config: Config = serde_yaml::from_str(indoc::indoc! {r#"
+                name: test
+                chain_id: croncat-test-1
+                sources: []
+                filters: []
+            "#})
+            .unwrap();
+            config.on_conflict = on_conflict;
+
+            let id = Uuid::new_v4();
+            save_block(
+                &config,
+                &db_transaction,
+                block_model(id, &"A".repeat(64)),
+                vec![transaction_response(&"C".repeat(64), 1)],
+            )
+            .await
+            .unwrap();
+
+            // The same block is indexed again, with another transaction matching the filters.
+            let saved = save_block(
+                &config,
+                &db_transaction,
+                block_model(Uuid::new_v4(), &"A".repeat(64)),
+                vec![transaction_response(&"D".repeat(64), 1)],
+            )
+            .await;
+            match on_conflict {
+                ConflictMode::Skip => assert!(saved.unwrap().is_none()),
+                ConflictMode::Overwrite => assert!(saved.unwrap().is_some()),
+                ConflictMode::Fail => assert!(matches!(
+                    saved,
+                    Err(IndexError::BlockConflict { height: 1, .. })
+                )),
+            }
+
+            // The block keeps its id, with the transactions of the write that won.
+            let block = BlockEntity::find_by_id((1, "croncat-test-1".to_string()))
+                .one(&db_transaction)
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(block.id, id);
+            let transactions = TransactionEntity::find()
+                .filter(TransactionColumn::BlockId.eq(id))
+                .all(&db_transaction)
+                .await
+                .unwrap();
+            let expected_hash = match on_conflict {
+                ConflictMode::Overwrite => "D".repeat(64),
+                ConflictMode::Skip | ConflictMode::Fail => "C".repeat(64),
+            };
+            assert_eq!(transactions.len(), 1, "{}", on_conflict);
+            assert_eq!(transactions[0].hash, expected_hash, "{}", on_conflict);
+
+            db_transaction.rollback().await.unwrap();
+        }
+    }
+
+    #[derive(Debug)]
+    struct TestDatabaseError(&'static str);
+
+    impl std::fmt::Display for TestDatabaseError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "database error {}", self.0)
+        }
+    }
+
+    impl std::error::Error for TestDatabaseError {}
+
+    impl sqlx::error::DatabaseError for TestDatabaseError {
+        fn message(&self) -> &str {
+            "localized message"
+        }
+
+        fn code(&self) -> Option<std::borrow::Cow<'_, str>> {
+            Some(self.0.into())
+        }
+
+        fn as_error(&self) -> &(dyn std::error::Error + Send + Sync + 'static) {
+            self
+        }
+
+        fn as_error_mut(&mut self) -> &mut (dyn std::error::Error + Send + Sync + 'static) {
+            self
+        }
+
+        fn into_error(self: Box<Self>) -> Box<dyn std::error::Error + Send + Sync + 'static> {
+            self
+        }
+    }
+
+    fn database_error(code: &'static str) -> RuntimeErr {
+        RuntimeErr::SqlxError(sqlx::Error::Database(Box::new(TestDatabaseError(code))))
+    }
+
+    #[test]
+    fn unique_violations() {
+        // Postgres unique violation.
+        assert!(is_unique_violation(&DbErr::Query(database_error("23505"))));
+        // SQLite unique and primary key violations.
+        assert!(is_unique_violation(&DbErr::Exec(database_error("2067"))));
+        assert!(is_unique_violation(&DbErr::Exec(database_error("1555"))));
+
+        // Postgres foreign key violation.
+        assert!(!is_unique_violation(&DbErr::Query(database_error("23503"))));
+        assert!(!is_unique_violation(&DbErr::Query(RuntimeErr::Internal(
+            "duplicate key value violates unique constraint".to_string()
+        ))));
+        assert!(!is_unique_violation(&DbErr::RecordNotFound(
+            "block".to_string()
+        )));
+    }
+
+    #[test]
+    fn block_indexed_payload_format() {
+        assert_eq!(block_indexed_payload("uni-5", 1234), "uni-5:1234");
+    }
+
+    #[test]
+    fn percentage_of_total() {
+        assert_eq!(percentage(0, 200), 0.0);
+        assert_eq!(percentage(50, 200), 25.0);
+        assert_eq!(percentage(200, 200), 100.0);
+        assert_eq!(percentage(0, 0), 100.0);
+        assert_eq!(format!("{:.1}", percentage(1, 3)), "33.3");
+    }
+
+    #[test]
+    fn transaction_hash_matches_tendermint() {
+        assert_eq!(
+            transaction_hash(b"croncat").to_string(),
+            "8130E6AA3A93B683F0465D61D1C4D3A9FF041283E5B8C40CAA1ABB659D8D10C8"
+        );
+    }
+
+    fn signed_tx(public_key: Vec<u8>) -> Vec<u8> {
+        use cosmos_sdk_proto::cosmos::base::v1beta1::Coin;
+        use cosmos_sdk_proto::cosmos::crypto::secp256k1::PubKey;
+        use cosmos_sdk_proto::cosmos::tx::v1beta1::{
+            mode_info, AuthInfo, Fee, ModeInfo, SignerInfo, Tx, TxBody,
+        };
+        use prost::Message;
+        use prost_types::Any;
+
+        Tx {
+            body: Some(TxBody::default()),
+            auth_info: Some(AuthInfo {
+                signer_infos: vec![SignerInfo {
+                    public_key: Some(Any {
+                        type_url: "/cosmos.crypto.secp256k1.PubKey".to_string(),
+                        value: PubKey { key: public_key }.encode_to_vec(),
+                    }),
+                    mode_info: Some(ModeInfo {
+                        sum: Some(mode_info::Sum::Single(mode_info::Single { mode: 1 })),
+                    }),
+                    sequence: 0,
+                }],
+                fee: Some(Fee {
+                    amount: vec![Coin {
+                        denom: "ujunox".to_string(),
+                        amount: "5000".to_string(),
+                    }],
+                    gas_limit: 200_000,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            signatures: vec![vec![0; 64]],
+        }
+        .encode_to_vec()
+    }
+
+    #[test]
+    fn fee_and_signer_decode() {
+        // The compressed secp256k1 generator point, a valid public key.
+        let public_key =
+            hex_literal("0279BE667EF9DCBBAC55A06295CE870B07029BFCDB2DCE28D959F2815B16F81798");
+        let tx = signed_tx(public_key);
+
+        let decoded = FeeAndSigner::decode(&tx, Some("juno")).unwrap();
+        assert_eq!(decoded.fee_amount.as_deref(), Some("5000"));
+        assert_eq!(decoded.fee_denom.as_deref(), Some("ujunox"));
+        assert!(decoded.signer.unwrap().starts_with("juno1"));
+
+        // Without an account prefix the signer is unknown.
+        let decoded = FeeAndSigner::decode(&tx, None).unwrap();
+        assert_eq!(decoded.fee_amount.as_deref(), Some("5000"));
+        assert_eq!(decoded.signer, None);
+
+        assert!(FeeAndSigner::decode(&[0xff, 0xff], Some("juno")).is_err());
+    }
+
+    fn hex_literal(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+}
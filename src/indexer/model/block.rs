@@ -14,6 +14,14 @@ pub struct Model {
     pub chain_id: String,
     pub hash: String,
     pub num_txs: i64,
+    pub proposer_address: Option<String>,
+    pub app_hash: Option<String>,
+    pub last_commit_hash: Option<String>,
+    pub total_gas_used: i64,
+    pub total_gas_wanted: i64,
+    pub source: Option<String>,
+    pub last_commit_signatures: Option<i32>,
+    pub last_commit_validators: Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -2,5 +2,10 @@
 
 pub mod prelude;
 
+pub mod backfill_job;
 pub mod block;
+pub mod block_signature;
+pub mod genesis_backfill;
+pub mod skipped_block;
 pub mod transaction;
+pub mod transaction_event;
@@ -1,4 +1,9 @@
 //! SeaORM Entity. Generated by sea-orm-codegen 0.9.3
 
+pub use super::backfill_job::Entity as BackfillJob;
 pub use super::block::Entity as Block;
+pub use super::block_signature::Entity as BlockSignature;
+pub use super::genesis_backfill::Entity as GenesisBackfill;
+pub use super::skipped_block::Entity as SkippedBlock;
 pub use super::transaction::Entity as Transaction;
+pub use super::transaction_event::Entity as TransactionEvent;
@@ -13,11 +13,14 @@ pub struct Model {
     pub code: i32,
     pub gas_wanted: String,
     pub gas_used: String,
-    pub events: Json,
+    pub events: Option<Json>,
     #[sea_orm(column_type = "Text")]
     pub log: String,
     #[sea_orm(column_type = "Text")]
     pub info: String,
+    pub fee_amount: Option<String>,
+    pub fee_denom: Option<String>,
+    pub signer: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -30,6 +33,8 @@ pub enum Relation {
         on_delete = "Cascade"
     )]
     Block,
+    #[sea_orm(has_many = "super::transaction_event::Entity")]
+    TransactionEvent,
 }
 
 impl Related<super::block::Entity> for Entity {
@@ -38,4 +43,10 @@ impl Related<super::block::Entity> for Entity {
     }
 }
 
+impl Related<super::transaction_event::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TransactionEvent.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,147 @@
+use chrono::{Duration, NaiveDateTime, Utc};
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, QuerySelect,
+    TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+
+use super::error::IndexResult;
+use super::model::block::Column as BlockColumn;
+use super::model::block::Entity as BlockEntity;
+use super::model::block_signature::Column as BlockSignatureColumn;
+use super::model::block_signature::Entity as BlockSignatureEntity;
+use super::model::skipped_block::Column as SkippedBlockColumn;
+use super::model::skipped_block::Entity as SkippedBlockEntity;
+use super::model::transaction::Column as TransactionColumn;
+use super::model::transaction::Entity as TransactionEntity;
+
+/// The number of blocks deleted per database transaction, keeping locks short.
+pub const PRUNE_BATCH_SIZE: u64 = 1000;
+
+///
+/// The number of rows removed by pruning.
+///
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PruneStats {
+    pub blocks: u64,
+    pub transactions: u64,
+    pub skipped_blocks: u64,
+    pub block_signatures: u64,
+}
+
+///
+/// The time before which data is pruned for a retention window in days.
+///
+pub fn retention_cutoff(retention_days: u64) -> NaiveDateTime {
+    Utc::now().naive_utc() - Duration::days(retention_days as i64)
+}
+
+///
+/// Delete the blocks, their transactions, the skipped blocks and the block signatures of a chain
+/// older than a cutoff, oldest first and `batch_size` blocks at a time.
+///
+pub async fn prune_blocks(
+    db: &DatabaseConnection,
+    chain_id: &str,
+    older_than: NaiveDateTime,
+    batch_size: u64,
+) -> IndexResult<PruneStats> {
+    let mut stats = PruneStats::default();
+
+    loop {
+        let block_ids = BlockEntity::find()
+            .filter(BlockColumn::ChainId.eq(chain_id))
+            .filter(BlockColumn::Time.lt(older_than))
+            .order_by_asc(BlockColumn::Height)
+            .limit(batch_size)
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|block| block.id)
+            .collect::<Vec<_>>();
+        if block_ids.is_empty() {
+            break;
+        }
+
+        // Remove each batch together, transactions first as they reference their block.
+        let db_transaction = db.begin().await?;
+        let transactions = TransactionEntity::delete_many()
+            .filter(TransactionColumn::BlockId.is_in(block_ids.clone()))
+            .exec(&db_transaction)
+            .await?;
+        let blocks = BlockEntity::delete_many()
+            .filter(BlockColumn::Id.is_in(block_ids))
+            .exec(&db_transaction)
+            .await?;
+        db_transaction.commit().await?;
+
+        stats.transactions += transactions.rows_affected;
+        stats.blocks += blocks.rows_affected;
+    }
+
+    loop {
+        let heights = SkippedBlockEntity::find()
+            .filter(SkippedBlockColumn::ChainId.eq(chain_id))
+            .filter(SkippedBlockColumn::Time.lt(older_than))
+            .order_by_asc(SkippedBlockColumn::Height)
+            .limit(batch_size)
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|skipped_block| skipped_block.height)
+            .collect::<Vec<_>>();
+        if heights.is_empty() {
+            break;
+        }
+
+        let skipped_blocks = SkippedBlockEntity::delete_many()
+            .filter(SkippedBlockColumn::ChainId.eq(chain_id))
+            .filter(SkippedBlockColumn::Height.is_in(heights))
+            .exec(db)
+            .await?;
+
+        stats.skipped_blocks += skipped_blocks.rows_affected;
+    }
+
+    loop {
+        // Every signature of a height goes at once, each batch covers up to `batch_size` of them.
+        let mut heights = BlockSignatureEntity::find()
+            .filter(BlockSignatureColumn::ChainId.eq(chain_id))
+            .filter(BlockSignatureColumn::Time.lt(older_than))
+            .order_by_asc(BlockSignatureColumn::Height)
+            .limit(batch_size)
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|block_signature| block_signature.height)
+            .collect::<Vec<_>>();
+        heights.dedup();
+        if heights.is_empty() {
+            break;
+        }
+
+        let block_signatures = BlockSignatureEntity::delete_many()
+            .filter(BlockSignatureColumn::ChainId.eq(chain_id))
+            .filter(BlockSignatureColumn::Height.is_in(heights))
+            .exec(db)
+            .await?;
+
+        stats.block_signatures += block_signatures.rows_affected;
+    }
+
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retention_cutoff_is_in_the_past() {
+        let now = Utc::now().naive_utc();
+        let cutoff = retention_cutoff(30);
+
+        assert!(cutoff < now - Duration::days(29));
+        assert!(cutoff > now - Duration::days(31));
+    }
+}
@@ -0,0 +1,120 @@
+use chrono::NaiveDateTime;
+use sea_orm::{DatabaseConnection, EntityTrait, ModelTrait, QueryOrder};
+use serde::{Deserialize, Serialize};
+
+use super::error::IndexResult;
+use super::model::block::Entity as BlockEntity;
+use super::model::block::Model as DatabaseBlock;
+use super::model::transaction::Column as TransactionColumn;
+use super::model::transaction::Entity as TransactionEntity;
+use super::model::transaction::Model as DatabaseTransaction;
+
+///
+/// A transaction exactly as it is stored.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredTransaction {
+    pub id: String,
+    pub block_id: String,
+    pub height: i64,
+    pub hash: String,
+    pub code: i32,
+    pub gas_wanted: String,
+    pub gas_used: String,
+    pub events: Option<serde_json::Value>,
+    pub log: String,
+    pub info: String,
+    pub fee_amount: Option<String>,
+    pub fee_denom: Option<String>,
+    pub signer: Option<String>,
+}
+
+impl From<DatabaseTransaction> for StoredTransaction {
+    fn from(transaction: DatabaseTransaction) -> Self {
+        Self {
+            id: transaction.id.to_string(),
+            block_id: transaction.block_id.to_string(),
+            height: transaction.height,
+            hash: transaction.hash,
+            code: transaction.code,
+            gas_wanted: transaction.gas_wanted,
+            gas_used: transaction.gas_used,
+            events: transaction.events,
+            log: transaction.log,
+            info: transaction.info,
+            fee_amount: transaction.fee_amount,
+            fee_denom: transaction.fee_denom,
+            signer: transaction.signer,
+        }
+    }
+}
+
+///
+/// A block exactly as it is stored, along with its transactions.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredBlock {
+    pub id: String,
+    pub chain_id: String,
+    pub height: i64,
+    pub time: NaiveDateTime,
+    pub hash: String,
+    pub num_txs: i64,
+    pub proposer_address: Option<String>,
+    pub app_hash: Option<String>,
+    pub last_commit_hash: Option<String>,
+    pub total_gas_used: i64,
+    pub total_gas_wanted: i64,
+    pub source: Option<String>,
+    pub last_commit_signatures: Option<i32>,
+    pub last_commit_validators: Option<i32>,
+    pub transactions: Vec<StoredTransaction>,
+}
+
+impl From<(DatabaseBlock, Vec<DatabaseTransaction>)> for StoredBlock {
+    fn from((block, transactions): (DatabaseBlock, Vec<DatabaseTransaction>)) -> Self {
+        Self {
+            id: block.id.to_string(),
+            chain_id: block.chain_id,
+            height: block.height,
+            time: block.time,
+            hash: block.hash,
+            num_txs: block.num_txs,
+            proposer_address: block.proposer_address,
+            app_hash: block.app_hash,
+            last_commit_hash: block.last_commit_hash,
+            total_gas_used: block.total_gas_used,
+            total_gas_wanted: block.total_gas_wanted,
+            source: block.source,
+            last_commit_signatures: block.last_commit_signatures,
+            last_commit_validators: block.last_commit_validators,
+            transactions: transactions.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+///
+/// Get the stored block of a chain at a height with its transactions ordered by hash, if it is
+/// indexed.
+///
+pub async fn get_stored_block(
+    db: &DatabaseConnection,
+    chain_id: &str,
+    height: i64,
+) -> IndexResult<Option<StoredBlock>> {
+    let block = match BlockEntity::find_by_id((height, chain_id.to_string()))
+        .one(db)
+        .await?
+    {
+        Some(block) => block,
+        None => return Ok(None),
+    };
+
+    let transactions = block
+        .find_related(TransactionEntity)
+        .order_by_asc(TransactionColumn::Hash)
+        .all(db)
+        .await?;
+
+    Ok(Some((block, transactions).into()))
+}
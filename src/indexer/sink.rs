@@ -0,0 +1,239 @@
+use chrono::NaiveDateTime;
+use color_eyre::Result;
+use serde::Serialize;
+use tendermint_rpc::endpoint::tx;
+use tracing::warn;
+
+use super::config::Config;
+use super::model::block::Model as DatabaseBlock;
+use super::model::transaction::ActiveModel as TransactionModel;
+use super::webhook::Webhook;
+
+///
+/// A message published for an indexed block or transaction.
+///
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SinkMessage {
+    Block {
+        chain_id: String,
+        height: i64,
+        hash: String,
+        time: NaiveDateTime,
+        num_txs: i64,
+    },
+    Transaction {
+        chain_id: String,
+        height: i64,
+        hash: String,
+        code: i32,
+        gas_wanted: String,
+        gas_used: String,
+        events: serde_json::Value,
+    },
+}
+
+impl SinkMessage {
+    ///
+    /// Describe an indexed block.
+    ///
+    pub fn block(block: &DatabaseBlock) -> Self {
+        Self::Block {
+            chain_id: block.chain_id.clone(),
+            height: block.height,
+            hash: block.hash.clone(),
+            time: block.time,
+            num_txs: block.num_txs,
+        }
+    }
+
+    ///
+    /// Describe an indexed transaction of a block.
+    ///
+    pub fn transaction(block: &DatabaseBlock, tx: &tx::Response) -> Result<Self> {
+        Ok(Self::Transaction {
+            chain_id: block.chain_id.clone(),
+            height: tx.height.value() as i64,
+            hash: tx.hash.to_string(),
+            code: tx.tx_result.code.value() as i32,
+            gas_wanted: tx.tx_result.gas_wanted.to_string(),
+            gas_used: tx.tx_result.gas_used.to_string(),
+            events: TransactionModel::decode_events(tx.tx_result.events.clone())?,
+        })
+    }
+}
+
+///
+/// Publish messages to a NATS subject.
+///
+#[cfg(feature = "nats")]
+#[derive(Clone)]
+struct NatsSink {
+    client: async_nats::Client,
+    subject: String,
+}
+
+#[cfg(feature = "nats")]
+impl NatsSink {
+    ///
+    /// Publish a message, logging failures instead of returning them.
+    ///
+    async fn publish(&self, message: &SinkMessage) {
+        let result = match serde_json::to_vec(message) {
+            Ok(payload) => self
+                .client
+                .publish(self.subject.clone(), payload.into())
+                .await
+                .map_err(|err| color_eyre::eyre::eyre!("{}", err)),
+            Err(err) => Err(err.into()),
+        };
+
+        if let Err(err) = result {
+            warn!("Failed to publish to {}: {}", self.subject, err);
+        }
+    }
+}
+
+///
+/// The sinks indexed blocks and transactions are published to, besides the database.
+///
+#[derive(Clone, Default)]
+pub struct Sinks {
+    #[cfg(feature = "nats")]
+    nats: Option<NatsSink>,
+    webhooks: Vec<Webhook>,
+}
+
+impl Sinks {
+    ///
+    /// Connect to the sinks of a configuration.
+    ///
+    #[cfg(feature = "nats")]
+    pub async fn connect(config: &Config) -> Result<Self> {
+        let webhooks = Webhook::spawn_all(config)?;
+        let nats = match &config.sink {
+            Some(sink) => {
+                let url = super::rpc::redact_url(sink.url.as_str());
+                tracing::info!(
+                    "[{}] Publishing to {} on {}",
+                    config.name,
+                    sink.subject,
+                    url
+                );
+                let client = async_nats::connect(sink.url.as_str())
+                    .await
+                    .map_err(|err| {
+                        color_eyre::eyre::eyre!("Failed to connect to {}: {}", url, err)
+                    })?;
+
+                Some(NatsSink {
+                    client,
+                    subject: sink.subject.clone(),
+                })
+            }
+            None => None,
+        };
+
+        Ok(Self { nats, webhooks })
+    }
+
+    ///
+    /// Connect to the sinks of a configuration.
+    ///
+    #[cfg(not(feature = "nats"))]
+    pub async fn connect(config: &Config) -> Result<Self> {
+        if config.sink.is_some() {
+            warn!(
+                "[{}] Ignoring the sink, the indexer was built without the nats feature",
+                config.name
+            );
+        }
+
+        Ok(Self {
+            webhooks: Webhook::spawn_all(config)?,
+        })
+    }
+
+    ///
+    /// Publish an indexed block and its newly indexed transactions.
+    ///
+    /// Failures are logged, indexing carries on without them.
+    ///
+    pub async fn publish(&self, block: &DatabaseBlock, txs: &[tx::Response]) {
+        for webhook in &self.webhooks {
+            for tx in txs {
+                webhook.notify(block, tx);
+            }
+        }
+
+        #[cfg(feature = "nats")]
+        if let Some(nats) = &self.nats {
+            nats.publish(&SinkMessage::block(block)).await;
+            for tx in txs {
+                match SinkMessage::transaction(block, tx) {
+                    Ok(message) => nats.publish(&message).await,
+                    Err(err) => warn!("Failed to describe transaction {}: {}", tx.hash, err),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use sea_orm::prelude::Uuid;
+
+    use super::*;
+    use crate::indexer::tests::{event, transaction_response};
+
+    #[test]
+    fn sink_message_serialize() {
+        let block = DatabaseBlock {
+            id: Uuid::nil(),
+            height: 10,
+            time: NaiveDate::from_ymd(2022, 10, 12).and_hms(14, 16, 5),
+            chain_id: "uni-5".to_string(),
+            hash: "A".repeat(64),
+            num_txs: 1,
+            proposer_address: None,
+            app_hash: None,
+            last_commit_hash: None,
+            total_gas_used: 0,
+            total_gas_wanted: 0,
+            source: None,
+            last_commit_signatures: None,
+            last_commit_validators: None,
+        };
+        let mut tx = transaction_response(&"B".repeat(64), 10);
+        tx.tx_result.events = vec![event("wasm", &[("_contract_address", "juno1croncat")])];
+
+        assert_eq!(
+            serde_json::to_value(SinkMessage::block(&block)).unwrap(),
+            serde_json::json!({
+                "type": "block",
+                "chain_id": "uni-5",
+                "height": 10,
+                "hash": "A".repeat(64),
+                "time": "2022-10-12T14:16:05",
+                "num_txs": 1,
+            })
+        );
+        assert_eq!(
+            serde_json::to_value(SinkMessage::transaction(&block, &tx).unwrap()).unwrap(),
+            serde_json::json!({
+                "type": "transaction",
+                "chain_id": "uni-5",
+                "height": 10,
+                "hash": "B".repeat(64),
+                "code": 0,
+                "gas_wanted": "0",
+                "gas_used": "0",
+                "events": [{
+                    "type": "wasm",
+                    "attributes": [{ "key": "_contract_address", "value": "juno1croncat" }],
+                }],
+            })
+        );
+    }
+}
@@ -0,0 +1,290 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Arc, RwLock};
+
+#[cfg(feature = "api")]
+use axum::{extract::Extension, http::StatusCode, routing::get, Json, Router};
+use chrono::NaiveDateTime;
+#[cfg(feature = "api")]
+use futures::future::try_join_all;
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, FromQueryResult, PaginatorTrait, QueryFilter,
+    QuerySelect,
+};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "api")]
+use tracing::error;
+use tracing::{info, warn};
+
+use super::error::{IndexError, IndexResult};
+use super::historical::{get_block_gaps, get_max_indexed_height, GAP_LOOKBACK_DAYS};
+use super::model::block::Column as BlockColumn;
+use super::model::block::Entity as BlockEntity;
+use super::model::transaction::Entity as TransactionEntity;
+
+///
+/// The indexing status of a chain.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainStatus {
+    pub chain_id: String,
+    pub max_indexed_height: Option<i64>,
+    pub total_blocks: u64,
+    pub total_transactions: u64,
+    pub open_gaps: usize,
+    pub oldest_block_time: Option<NaiveDateTime>,
+    pub newest_block_time: Option<NaiveDateTime>,
+}
+
+///
+/// The time of the oldest and newest indexed blocks of a chain.
+///
+#[derive(Debug, Clone, PartialEq, Eq, FromQueryResult)]
+pub struct BlockTimeRange {
+    pub oldest: Option<NaiveDateTime>,
+    pub newest: Option<NaiveDateTime>,
+}
+
+///
+/// Count the indexed blocks of a chain.
+///
+pub async fn get_total_blocks(db: &DatabaseConnection, chain_id: &str) -> IndexResult<u64> {
+    BlockEntity::find()
+        .filter(BlockColumn::ChainId.eq(chain_id))
+        .count(db)
+        .await
+        .map_err(|err| err.into())
+}
+
+///
+/// Count the indexed transactions of a chain.
+///
+pub async fn get_total_transactions(db: &DatabaseConnection, chain_id: &str) -> IndexResult<u64> {
+    TransactionEntity::find()
+        .inner_join(BlockEntity)
+        .filter(BlockColumn::ChainId.eq(chain_id))
+        .count(db)
+        .await
+        .map_err(|err| err.into())
+}
+
+///
+/// Count the gaps of a chain within the historical indexer's lookback, ignoring the
+/// `start_height` of its configurations.
+///
+pub async fn get_open_gaps(db: &DatabaseConnection, chain_id: &str) -> IndexResult<usize> {
+    Ok(
+        get_block_gaps(db, chain_id.to_string(), GAP_LOOKBACK_DAYS, None)
+            .await?
+            .len(),
+    )
+}
+
+///
+/// Get the time of the oldest and newest indexed blocks of a chain.
+///
+pub async fn get_block_time_range(
+    db: &DatabaseConnection,
+    chain_id: &str,
+) -> IndexResult<BlockTimeRange> {
+    let range = BlockEntity::find()
+        .select_only()
+        .column_as(Expr::col(BlockColumn::Time).min(), "oldest")
+        .column_as(Expr::col(BlockColumn::Time).max(), "newest")
+        .filter(BlockColumn::ChainId.eq(chain_id))
+        .into_model::<BlockTimeRange>()
+        .one(db)
+        .await?;
+
+    Ok(range.unwrap_or(BlockTimeRange {
+        oldest: None,
+        newest: None,
+    }))
+}
+
+///
+/// Get the indexing status of a chain.
+///
+pub async fn get_chain_status(db: &DatabaseConnection, chain_id: &str) -> IndexResult<ChainStatus> {
+    let BlockTimeRange { oldest, newest } = get_block_time_range(db, chain_id).await?;
+
+    Ok(ChainStatus {
+        chain_id: chain_id.to_string(),
+        max_indexed_height: get_max_indexed_height(db, chain_id).await?,
+        total_blocks: get_total_blocks(db, chain_id).await?,
+        total_transactions: get_total_transactions(db, chain_id).await?,
+        open_gaps: get_open_gaps(db, chain_id).await?,
+        oldest_block_time: oldest,
+        newest_block_time: newest,
+    })
+}
+
+///
+/// Describe the indexed blocks of a chain in a single log line.
+///
+pub fn startup_summary(
+    chain_id: &str,
+    total_blocks: u64,
+    max_indexed_height: Option<i64>,
+    open_gaps: usize,
+) -> String {
+    match max_indexed_height {
+        Some(height) => format!(
+            "Chain {} has {} indexed blocks up to height {} with {} open gaps",
+            chain_id, total_blocks, height, open_gaps
+        ),
+        None => format!("Chain {} has no indexed blocks yet", chain_id),
+    }
+}
+
+///
+/// Log the total blocks, highest indexed height and open gaps of every chain. This is
+/// best-effort, a failing query is logged and skipped.
+///
+pub async fn log_startup_summary(db: &DatabaseConnection, chain_ids: &[String]) {
+    for chain_id in chain_ids {
+        let summary = async {
+            Ok::<_, IndexError>(startup_summary(
+                chain_id,
+                get_total_blocks(db, chain_id).await?,
+                get_max_indexed_height(db, chain_id).await?,
+                get_open_gaps(db, chain_id).await?,
+            ))
+        };
+        match summary.await {
+            Ok(summary) => info!("{}", summary),
+            Err(err) => warn!(
+                "Failed to summarize the indexed blocks of {}: {}",
+                chain_id, err
+            ),
+        }
+    }
+}
+
+///
+/// The latest ingestion lag of each chain in blocks, recorded by the lag monitors and served as
+/// metrics.
+///
+pub type IngestionLag = Arc<RwLock<BTreeMap<String, u64>>>;
+
+///
+/// The number of blocks between the latest height of a chain and its highest indexed one, the
+/// whole chain when nothing is indexed yet.
+///
+pub fn ingestion_lag_blocks(latest_height: i64, max_indexed_height: Option<i64>) -> u64 {
+    latest_height
+        .saturating_sub(max_indexed_height.unwrap_or(0))
+        .max(0) as u64
+}
+
+///
+/// Render the ingestion lag of every chain in the Prometheus text format.
+///
+pub fn render_metrics(ingestion_lag: &BTreeMap<String, u64>) -> String {
+    let mut metrics = String::from(
+        "# HELP ingestion_lag_blocks Blocks between the latest height of the chain and the highest \
+         indexed one.\n\
+         # TYPE ingestion_lag_blocks gauge\n",
+    );
+    for (chain_id, lag) in ingestion_lag {
+        let _ = writeln!(
+            metrics,
+            "ingestion_lag_blocks{{chain_id=\"{}\"}} {}",
+            chain_id, lag
+        );
+    }
+
+    metrics
+}
+
+///
+/// The state shared by the status handlers.
+///
+#[derive(Debug, Clone)]
+pub struct StatusState {
+    pub db: DatabaseConnection,
+    pub chain_ids: Vec<String>,
+    pub ingestion_lag: IngestionLag,
+}
+
+///
+/// Serve the status of every chain as JSON.
+///
+#[cfg(feature = "api")]
+async fn status(
+    Extension(state): Extension<Arc<StatusState>>,
+) -> Result<Json<Vec<ChainStatus>>, (StatusCode, String)> {
+    try_join_all(
+        state
+            .chain_ids
+            .iter()
+            .map(|chain_id| get_chain_status(&state.db, chain_id)),
+    )
+    .await
+    .map(Json)
+    .map_err(|err| {
+        error!("Failed to get the indexing status: {}", err);
+        (StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
+    })
+}
+
+///
+/// Serve the ingestion lag of every chain as Prometheus metrics.
+///
+#[cfg(feature = "api")]
+async fn metrics(Extension(state): Extension<Arc<StatusState>>) -> String {
+    render_metrics(&state.ingestion_lag.read().unwrap())
+}
+
+///
+/// Build the status routes, served along with the query api (see [`super::api::serve`]).
+///
+#[cfg(feature = "api")]
+pub fn router(state: StatusState) -> Router {
+    Router::new()
+        .route("/status", get(status))
+        .route("/metrics", get(metrics))
+        .layer(Extension(Arc::new(state)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ingestion_lag_blocks_of_heights() {
+        assert_eq!(ingestion_lag_blocks(120, Some(100)), 20);
+        assert_eq!(ingestion_lag_blocks(100, Some(100)), 0);
+        assert_eq!(ingestion_lag_blocks(120, None), 120);
+
+        // Another indexer of the chain may be ahead of the node we asked.
+        assert_eq!(ingestion_lag_blocks(100, Some(102)), 0);
+    }
+
+    #[test]
+    fn startup_summary_of_chains() {
+        assert_eq!(
+            startup_summary("uni-5", 120, Some(1234), 2),
+            "Chain uni-5 has 120 indexed blocks up to height 1234 with 2 open gaps"
+        );
+        assert_eq!(
+            startup_summary("uni-5", 0, None, 0),
+            "Chain uni-5 has no indexed blocks yet"
+        );
+    }
+
+    #[test]
+    fn render_metrics_of_chains() {
+        let ingestion_lag = BTreeMap::from([("uni-5".to_string(), 3), ("juno-1".to_string(), 0)]);
+
+        assert_eq!(
+            render_metrics(&ingestion_lag),
+            "# HELP ingestion_lag_blocks Blocks between the latest height of the chain and the \
+             highest indexed one.\n\
+             # TYPE ingestion_lag_blocks gauge\n\
+             ingestion_lag_blocks{chain_id=\"juno-1\"} 0\n\
+             ingestion_lag_blocks{chain_id=\"uni-5\"} 3\n"
+        );
+    }
+}
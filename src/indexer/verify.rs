@@ -0,0 +1,154 @@
+use std::time::Duration;
+
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+use serde::{Deserialize, Serialize};
+use tendermint_rpc::Client;
+use tokio::time::{interval, MissedTickBehavior};
+use tracing::{trace, warn};
+
+use super::error::{IndexError, IndexResult};
+use super::model::block::Column as BlockColumn;
+use super::model::block::Entity as BlockEntity;
+use super::rpc::{self, RpcPool};
+
+/// The number of stored blocks loaded per query while verifying them.
+pub const VERIFY_BATCH_SIZE: u64 = 100;
+
+///
+/// A stored block whose hash differs from the one of the block at its height on the chain.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HashMismatch {
+    pub height: i64,
+    pub stored: String,
+    pub actual: String,
+}
+
+///
+/// The outcome of verifying the stored blocks of a height range.
+///
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerifyReport {
+    /// The number of stored blocks compared with the chain.
+    pub verified: u64,
+    /// The stored blocks that don't match the chain.
+    pub mismatches: Vec<HashMismatch>,
+}
+
+///
+/// Compare a stored block hash with the hash computed from the block at its height on the chain,
+/// ignoring case.
+///
+pub fn hash_mismatch(height: i64, stored: &str, block: &tendermint::Block) -> Option<HashMismatch> {
+    let actual = block.header().hash().to_string();
+    if actual.eq_ignore_ascii_case(stored) {
+        return None;
+    }
+
+    Some(HashMismatch {
+        height,
+        stored: stored.to_string(),
+        actual,
+    })
+}
+
+///
+/// The time between two rpc requests to make at most `rate` of them per second.
+///
+pub fn request_period(rate: u32) -> Duration {
+    Duration::from_secs(1) / rate.max(1)
+}
+
+///
+/// Fetch each stored block of a chain between two heights (inclusive) again, at most `rate` per
+/// second, and compare its stored hash with the hash of the fetched block.
+///
+pub async fn verify_blocks<C>(
+    db: &DatabaseConnection,
+    rpc_pool: &RpcPool<C>,
+    chain_id: &str,
+    from: i64,
+    to: i64,
+    rate: u32,
+) -> IndexResult<VerifyReport>
+where
+    C: Client + Sync,
+{
+    if from > to {
+        return Err(IndexError::invalid_data(format!(
+            "Invalid height range {} to {}",
+            from, to
+        )));
+    }
+
+    let mut report = VerifyReport::default();
+    let mut requests = interval(request_period(rate));
+    requests.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    let mut next_height = from;
+    loop {
+        let blocks = BlockEntity::find()
+            .filter(BlockColumn::ChainId.eq(chain_id))
+            .filter(BlockColumn::Height.between(next_height, to))
+            .order_by_asc(BlockColumn::Height)
+            .limit(VERIFY_BATCH_SIZE)
+            .all(db)
+            .await?;
+        next_height = match blocks.last() {
+            Some(block) => block.height + 1,
+            None => break,
+        };
+
+        for block in blocks {
+            requests.tick().await;
+            let height = block.height;
+            let fetched = rpc_pool
+                .with_failover(|client| rpc::get_block(client, height))
+                .await?;
+            report.verified += 1;
+
+            match hash_mismatch(height, &block.hash, &fetched) {
+                Some(mismatch) => {
+                    warn!(
+                        "Block {} on {} is stored with hash {} but the chain has {}",
+                        height, chain_id, mismatch.stored, mismatch.actual
+                    );
+                    report.mismatches.push(mismatch);
+                }
+                None => trace!("Block {} on {} matches the chain", height, chain_id),
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::streams::fixture::test_block;
+
+    #[test]
+    fn hash_mismatch_of_stored_hash() {
+        let block = test_block(1).inner;
+        let hash = block.header().hash().to_string();
+
+        assert_eq!(hash_mismatch(1, &hash, &block), None);
+        assert_eq!(hash_mismatch(1, &hash.to_lowercase(), &block), None);
+        assert_eq!(
+            hash_mismatch(1, &"0".repeat(64), &block),
+            Some(HashMismatch {
+                height: 1,
+                stored: "0".repeat(64),
+                actual: hash,
+            })
+        );
+    }
+
+    #[test]
+    fn request_period_of_rate() {
+        assert_eq!(request_period(10), Duration::from_millis(100));
+        assert_eq!(request_period(1), Duration::from_secs(1));
+        assert_eq!(request_period(0), Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,59 @@
+use std::path::{Path, PathBuf};
+
+use color_eyre::Result;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use tokio::sync::mpsc;
+use tracing::error;
+
+///
+/// Whether a path is a configuration file, loaded from `*.config.yaml`.
+///
+pub fn is_config_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .map_or(false, |name| name.ends_with(".config.yaml"))
+}
+
+///
+/// Watch the configuration files of a directory, sending the path of each one created or modified
+/// until the returned watcher is dropped.
+///
+pub fn watch_configs(
+    dir: &Path,
+    changes: mpsc::UnboundedSender<PathBuf>,
+) -> Result<RecommendedWatcher> {
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+        let event = match event {
+            Ok(event) => event,
+            Err(err) => {
+                error!("Failed to watch the configuration files: {}", err);
+                return;
+            }
+        };
+        if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+            return;
+        }
+
+        for path in event.paths.into_iter().filter(|path| is_config_file(path)) {
+            // Nothing listens anymore once the indexers stopped.
+            let _ = changes.send(path);
+        }
+    })?;
+    watcher.watch(dir, RecursiveMode::NonRecursive)?;
+
+    Ok(watcher)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_config_file_by_name() {
+        assert!(is_config_file(Path::new("/etc/indexer/uni.config.yaml")));
+        assert!(is_config_file(Path::new("./juno.config.yaml")));
+        assert!(!is_config_file(Path::new("./juno.config.yaml.swp")));
+        assert!(!is_config_file(Path::new("./example.yaml")));
+        assert!(!is_config_file(Path::new("/")));
+    }
+}
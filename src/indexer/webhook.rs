@@ -0,0 +1,296 @@
+use color_eyre::{eyre::eyre, Result};
+use serde::Serialize;
+use tendermint_rpc::endpoint::tx;
+use tokio::sync::mpsc;
+use tokio_retry::Retry;
+use tracing::{trace, warn};
+
+use super::config::filter::{Filter, FilterKind};
+use super::config::webhook::WebhookConfig;
+use super::config::Config;
+use super::model::block::Model as DatabaseBlock;
+use super::model::transaction::ActiveModel as TransactionModel;
+
+///
+/// The notification POSTed to a webhook for an indexed transaction.
+///
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WebhookPayload {
+    /// A human readable summary, displayed by Slack compatible webhooks.
+    pub text: String,
+    pub chain_id: String,
+    pub height: i64,
+    pub hash: String,
+    /// The names of the filters that triggered the webhook.
+    pub filters: Vec<String>,
+    /// The events matching those filters.
+    pub events: serde_json::Value,
+}
+
+impl WebhookPayload {
+    ///
+    /// Describe a transaction of a block and the filters it matched.
+    ///
+    pub fn new(block: &DatabaseBlock, tx: &tx::Response, filters: &[&Filter]) -> Result<Self> {
+        let names = filters
+            .iter()
+            .filter_map(|filter| filter.name.clone())
+            .collect::<Vec<_>>();
+        let events = tx
+            .tx_result
+            .events
+            .iter()
+            .filter(|event| {
+                filters.iter().any(|filter| {
+                    filter.kind == FilterKind::Event
+                        && !filter.negate
+                        && filter.matches_event(event)
+                })
+            })
+            .cloned()
+            .collect();
+
+        let mut text = format!(
+            "Indexed transaction {} at height {} on {}",
+            tx.hash, block.height, block.chain_id
+        );
+        if !names.is_empty() {
+            text.push_str(&format!(" matching {}", names.join(", ")));
+        }
+
+        Ok(Self {
+            text,
+            chain_id: block.chain_id.clone(),
+            height: block.height,
+            hash: tx.hash.to_string(),
+            filters: names,
+            events: TransactionModel::decode_events(events)?,
+        })
+    }
+}
+
+///
+/// A webhook with a bounded queue of notifications, sent by a background task so a slow webhook
+/// never holds up indexing.
+///
+#[derive(Clone)]
+pub struct Webhook {
+    filters: Vec<Filter>,
+    queue: mpsc::Sender<WebhookPayload>,
+    url: String,
+}
+
+impl Webhook {
+    ///
+    /// Start sending the notifications of a configured webhook.
+    ///
+    pub fn spawn(config: &Config, webhook: &WebhookConfig) -> Result<Self> {
+        let filters = webhook
+            .filters
+            .iter()
+            .map(|name| {
+                config
+                    .filters
+                    .iter()
+                    .find(|filter| filter.name.as_ref() == Some(name))
+                    .cloned()
+                    .ok_or_else(|| eyre!("Webhook refers to unknown filter {}", name))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let (queue, mut notifications) = mpsc::channel::<WebhookPayload>(webhook.queue_size);
+        let client = reqwest::Client::builder()
+            .timeout(webhook.timeout())
+            .build()
+            .map_err(|err| eyre!("Invalid webhook client: {}", err))?;
+        let url = webhook.url.clone();
+        let retry = webhook.retry.clone();
+        let redacted_url = webhook.redacted_url();
+
+        // The task stops once every clone of the webhook is dropped.
+        let task_url = redacted_url.clone();
+        tokio::spawn(async move {
+            while let Some(payload) = notifications.recv().await {
+                let result = Retry::spawn(retry.strategy(), || async {
+                    client
+                        .post(url.clone())
+                        .json(&payload)
+                        .send()
+                        .await?
+                        .error_for_status()
+                })
+                .await;
+
+                match result {
+                    Ok(_) => trace!("Notified {} of transaction {}", task_url, payload.hash),
+                    Err(err) => warn!(
+                        "Failed to notify {} of transaction {}: {}",
+                        task_url, payload.hash, err
+                    ),
+                }
+            }
+        });
+
+        Ok(Self {
+            filters,
+            queue,
+            url: redacted_url,
+        })
+    }
+
+    ///
+    /// Start sending the notifications of every webhook of a configuration.
+    ///
+    pub fn spawn_all(config: &Config) -> Result<Vec<Self>> {
+        config
+            .webhooks
+            .iter()
+            .map(|webhook| Self::spawn(config, webhook))
+            .collect()
+    }
+
+    ///
+    /// The filters of the webhook a transaction matches, `None` when it doesn't trigger the webhook.
+    ///
+    fn matched_filters(&self, tx: &tx::Response) -> Option<Vec<&Filter>> {
+        if self.filters.is_empty() {
+            return Some(vec![]);
+        }
+
+        let matched = self
+            .filters
+            .iter()
+            .filter(|filter| filter.matches(tx))
+            .collect::<Vec<_>>();
+        (!matched.is_empty()).then_some(matched)
+    }
+
+    ///
+    /// Queue a notification for an indexed transaction if it triggers the webhook.
+    ///
+    /// The notification is dropped when the queue is full.
+    ///
+    pub fn notify(&self, block: &DatabaseBlock, tx: &tx::Response) {
+        let filters = match self.matched_filters(tx) {
+            Some(filters) => filters,
+            None => return,
+        };
+
+        let payload = match WebhookPayload::new(block, tx, &filters) {
+            Ok(payload) => payload,
+            Err(err) => {
+                warn!("Failed to describe transaction {}: {}", tx.hash, err);
+                return;
+            }
+        };
+
+        if let Err(err) = self.queue.try_send(payload) {
+            warn!(
+                "Dropping notification of transaction {} for {}: {}",
+                tx.hash, self.url, err
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use sea_orm::prelude::Uuid;
+
+    use super::*;
+    use crate::indexer::tests::{event, transaction_response};
+
+    fn test_block() -> DatabaseBlock {
+        DatabaseBlock {
+            id: Uuid::nil(),
+            height: 10,
+            time: NaiveDate::from_ymd(2022, 10, 12).and_hms(14, 16, 5),
+            chain_id: "uni-5".to_string(),
+            hash: "A".repeat(64),
+            num_txs: 1,
+            proposer_address: None,
+            app_hash: None,
+            last_commit_hash: None,
+            total_gas_used: 0,
+            total_gas_wanted: 0,
+            source: None,
+            last_commit_signatures: None,
+            last_commit_validators: None,
+        }
+    }
+
+    fn croncat_filter() -> Filter {
+        serde_yaml::from_str(indoc::indoc! {r#"
+            name: croncat
+            type: wasm
+            attributes:
+              - key: _contract_address
+                value: ^juno1croncat$
+        "#})
+        .unwrap()
+    }
+
+    fn test_webhook(filters: Vec<Filter>) -> (Webhook, mpsc::Receiver<WebhookPayload>) {
+        let (queue, notifications) = mpsc::channel(1);
+        let webhook = Webhook {
+            filters,
+            queue,
+            url: "https://hooks.example.com/***".to_string(),
+        };
+        (webhook, notifications)
+    }
+
+    #[test]
+    fn webhook_payload() {
+        let mut tx = transaction_response(&"B".repeat(64), 10);
+        tx.tx_result.events = vec![
+            event("message", &[("action", "execute")]),
+            event("wasm", &[("_contract_address", "juno1croncat")]),
+        ];
+        let filter = croncat_filter();
+
+        let payload = WebhookPayload::new(&test_block(), &tx, &[&filter]).unwrap();
+
+        assert_eq!(
+            serde_json::to_value(payload).unwrap(),
+            serde_json::json!({
+                "text": format!("Indexed transaction {} at height 10 on uni-5 matching croncat", "B".repeat(64)),
+                "chain_id": "uni-5",
+                "height": 10,
+                "hash": "B".repeat(64),
+                "filters": ["croncat"],
+                "events": [{
+                    "type": "wasm",
+                    "attributes": [{ "key": "_contract_address", "value": "juno1croncat" }],
+                }],
+            })
+        );
+    }
+
+    #[test]
+    fn webhook_notify_matching_transactions() {
+        let (webhook, mut notifications) = test_webhook(vec![croncat_filter()]);
+
+        let mut tx = transaction_response(&"B".repeat(64), 10);
+        tx.tx_result.events = vec![event("wasm", &[("_contract_address", "juno1other")])];
+        webhook.notify(&test_block(), &tx);
+        assert!(notifications.try_recv().is_err());
+
+        tx.tx_result.events = vec![event("wasm", &[("_contract_address", "juno1croncat")])];
+        webhook.notify(&test_block(), &tx);
+        assert_eq!(notifications.try_recv().unwrap().filters, vec!["croncat"]);
+    }
+
+    #[test]
+    fn webhook_notify_drops_when_full() {
+        let (webhook, mut notifications) = test_webhook(vec![]);
+
+        // Without filters every transaction triggers the webhook.
+        webhook.notify(&test_block(), &transaction_response(&"B".repeat(64), 10));
+        webhook.notify(&test_block(), &transaction_response(&"C".repeat(64), 10));
+
+        assert_eq!(notifications.try_recv().unwrap().hash, "B".repeat(64));
+        assert!(notifications.try_recv().is_err());
+    }
+}
@@ -1,13 +1,39 @@
 #![feature(type_alias_impl_trait)]
 
+use cli::{Command, Opt};
 use color_eyre::Result;
+use structopt::StructOpt;
 
+pub mod cli;
 pub mod env;
 pub mod indexer;
 pub mod streams;
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    let opt = Opt::from_args();
     env::setup()?;
-    indexer::system::run_all().await
+
+    match opt.command.unwrap_or(Command::Run) {
+        Command::Run => indexer::system::run_all(opt.dry_run, opt.only.as_deref()).await,
+        Command::Backfill => indexer::system::backfill_all(opt.dry_run, opt.only.as_deref()).await,
+        Command::Reindex {
+            chain_id,
+            from,
+            to,
+            dry_run,
+        } => indexer::system::reindex(&chain_id, from, to, dry_run || opt.dry_run).await,
+        Command::Prune {
+            chain_id,
+            older_than,
+        } => indexer::system::prune(&chain_id, older_than).await,
+        Command::Gaps { chain_id, days } => indexer::system::gaps(&chain_id, days).await,
+        Command::Verify {
+            chain_id,
+            from,
+            to,
+            rate,
+        } => indexer::system::verify(&chain_id, from, to, rate).await,
+        Command::Show { chain_id, height } => indexer::system::show(&chain_id, height).await,
+    }
 }
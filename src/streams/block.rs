@@ -1,16 +1,22 @@
-use std::{pin::Pin, time::Duration};
+use std::{ops::RangeInclusive, pin::Pin, time::Duration};
 
 use async_stream::try_stream;
-use color_eyre::{Report, Result};
+use color_eyre::{eyre::eyre, Report, Result};
 use delegate::delegate;
 use futures::{StreamExt, TryStream};
+use tendermint::block::Height;
 use tendermint_rpc::{
-    event::EventData, query::EventType, HttpClient, SubscriptionClient, WebSocketClient,
+    event::EventData, query::EventType, Client, Subscription, SubscriptionClient, WebSocketClient,
 };
+use tokio::task::{JoinError, JoinHandle};
 use tokio::time::timeout;
-use tracing::trace;
+use tokio_util::sync::CancellationToken;
+use tracing::{trace, warn};
+use url::Url;
 
-use crate::indexer::{rpc, BlockError};
+use crate::indexer::config::retry::RetryConfig;
+use crate::indexer::rpc::{self, HttpClientOptions, HttpRpcClient};
+use crate::indexer::BlockError;
 
 ///
 /// Block wrapper
@@ -18,6 +24,8 @@ use crate::indexer::{rpc, BlockError};
 #[derive(Debug, Clone)]
 pub struct Block {
     pub inner: tendermint::Block,
+    /// The name of the source that produced the block, if it came from one.
+    pub source: Option<String>,
 }
 
 #[allow(dead_code)]
@@ -26,13 +34,25 @@ impl Block {
         to self.inner {
             pub fn header(&self) -> &tendermint::block::Header;
             pub fn data(&self) -> &tendermint::abci::transaction::Data;
+            pub fn last_commit(&self) -> &Option<tendermint::block::Commit>;
         }
     }
+
+    ///
+    /// Record the name of the source that produced the block.
+    ///
+    pub fn with_source(mut self, source: impl Into<String>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
 }
 
 impl From<tendermint::Block> for Block {
     fn from(block: tendermint::Block) -> Self {
-        Self { inner: block }
+        Self {
+            inner: block,
+            source: None,
+        }
     }
 }
 
@@ -56,59 +76,597 @@ impl PartialEq for Block {
 
 impl Eq for Block {}
 
-type BlockStream = Pin<Box<dyn TryStream<Item = Result<Block>, Ok = Block, Error = Report> + Send>>;
+pub type BlockStream =
+    Pin<Box<dyn TryStream<Item = Result<Block>, Ok = Block, Error = Report> + Send>>;
+
+///
+/// Connect to the websocket rpc endpoint and subscribe to new blocks.
+///
+async fn ws_subscribe(
+    ws_rpc_host: &str,
+) -> Result<(
+    WebSocketClient,
+    JoinHandle<Result<(), tendermint_rpc::Error>>,
+    Subscription,
+)> {
+    let (client, driver) =
+        WebSocketClient::new(ws_rpc_host)
+            .await
+            .map_err(|source| BlockError::Connect {
+                source: source.into(),
+            })?;
+    let driver_handle = tokio::spawn(async move { driver.run().await });
+
+    match client.subscribe(EventType::NewBlock.into()).await {
+        Ok(subscription) => Ok((client, driver_handle, subscription)),
+        Err(source) => {
+            ws_close(ws_rpc_host, client, driver_handle).await;
+            Err(BlockError::Subscribe {
+                source: source.into(),
+            }
+            .into())
+        }
+    }
+}
+
+///
+/// Close a websocket connection, only tracing failures as the connection is going away anyway.
+///
+async fn ws_close(
+    ws_rpc_host: &str,
+    client: WebSocketClient,
+    driver_handle: JoinHandle<Result<(), tendermint_rpc::Error>>,
+) {
+    let ws_rpc_host = rpc::redact_url(ws_rpc_host);
+    trace!("Closing websocket connection to {}", ws_rpc_host);
+    if let Err(err) = client.close() {
+        trace!(
+            "Failed to close websocket connection to {}: {}",
+            ws_rpc_host,
+            err
+        );
+    }
+    match driver_handle.await {
+        Ok(Err(err)) => trace!("Websocket driver for {} failed: {}", ws_rpc_host, err),
+        Err(err) => trace!("Websocket driver for {} panicked: {}", ws_rpc_host, err),
+        Ok(Ok(())) => {}
+    }
+}
+
+///
+/// The error of a websocket driver that stopped while its subscription was still in use.
+///
+fn ws_driver_error(result: Result<Result<(), tendermint_rpc::Error>, JoinError>) -> Report {
+    let source = match result {
+        Ok(Ok(())) => eyre!("the connection was closed"),
+        Ok(Err(err)) => Report::from(err),
+        Err(err) if err.is_panic() => eyre!("the driver panicked: {}", err),
+        Err(err) => eyre!("the driver was cancelled: {}", err),
+    };
+
+    BlockError::DriverStopped { source }.into()
+}
+
+///
+/// Stream blocks from the given rpc endpoint until the shutdown token is cancelled, reconnecting
+/// when no event arrives within `recv_timeout`.
+///
+/// Dropped connections are reconnected with the default [`RetryConfig::reconnect_default`] backoff.
+///
+pub fn ws_block_stream(
+    ws_rpc_host: String,
+    recv_timeout: Duration,
+    shutdown: CancellationToken,
+) -> BlockStream {
+    ws_block_stream_with_retry(
+        ws_rpc_host,
+        recv_timeout,
+        RetryConfig::reconnect_default(),
+        shutdown,
+    )
+}
 
 ///
-/// Stream blocks from the given rpc endpoint.
+/// Stream blocks from the given rpc endpoint until the shutdown token is cancelled, reconnecting
+/// with the given backoff and only failing once every attempt since the last block failed.
 ///
-pub fn ws_block_stream(ws_rpc_host: String) -> BlockStream {
+pub fn ws_block_stream_with_retry(
+    ws_rpc_host: String,
+    recv_timeout: Duration,
+    retry: RetryConfig,
+    shutdown: CancellationToken,
+) -> BlockStream {
     Box::pin(try_stream! {
-        let (client, driver) = WebSocketClient::new(ws_rpc_host.as_str()).await.map_err(|source| BlockError::Connect { source: source.into() })?;
-        let driver_handle = tokio::spawn(async move {
-            driver.run().await
-        });
+        let redacted_host = rpc::redact_url(&ws_rpc_host);
+        let mut delays = retry.strategy();
+        let mut failed_attempts = 0;
 
-        let mut subscription = client.subscribe(EventType::NewBlock.into()).await.map_err(|source| BlockError::Subscribe { source: source.into() })?;
+        'connection: loop {
+            // Any disconnect reconnects after the next delay, unless we ran out of attempts.
+            let error = match ws_subscribe(&ws_rpc_host).await {
+                Ok((client, mut driver_handle, mut subscription)) => {
+                    let mut driver_stopped = false;
+                    let error = loop {
+                        let event = tokio::select! {
+                            biased;
+                            _ = shutdown.cancelled() => {
+                                ws_close(&ws_rpc_host, client, driver_handle).await;
+                                break 'connection;
+                            },
+                            // Without its driver the subscription would hang until the recv timeout.
+                            result = &mut driver_handle => {
+                                driver_stopped = true;
+                                break ws_driver_error(result);
+                            },
+                            event = timeout(recv_timeout, subscription.next()) => event,
+                        };
 
-        let recv_timeout_duration = Duration::from_secs(60);
-        while let Some(event) =
-            timeout(recv_timeout_duration, subscription.next())
-            .await
-            .map_err(|_| BlockError::Timeout { timeout: recv_timeout_duration })?
-        {
-            let event = event.map_err(|err| BlockError::TendermintError { source: err })?;
-            let data = event.data;
-
-            match data {
-                EventData::NewBlock { block, .. } => {
-                    let block = block.ok_or_else(|| BlockError::EventWithoutBlock)?;
-                    trace!("Received block {} ({})", block.header().height, block.header().chain_id);
-                    yield block.into();
+                        let event = match event {
+                            Ok(Some(Ok(event))) => event,
+                            Ok(Some(Err(source))) => break Report::from(BlockError::TendermintError { source }),
+                            Ok(None) => break eyre!("Subscription to {} closed", redacted_host),
+                            Err(_) => break BlockError::Timeout { timeout: recv_timeout }.into(),
+                        };
+
+                        match event.data {
+                            EventData::NewBlock { block: Some(block), .. } => {
+                                // A healthy connection starts the backoff over.
+                                failed_attempts = 0;
+                                delays = retry.strategy();
+
+                                trace!("Received block {} ({})", block.header().height, block.header().chain_id);
+                                yield block.into();
+                            },
+                            EventData::NewBlock { block: None, .. } => warn!("{}", BlockError::EventWithoutBlock),
+                            _ => continue,
+                        }
+                    };
+                    // A stopped driver has nothing left to close, and its handle was already awaited.
+                    if !driver_stopped {
+                        ws_close(&ws_rpc_host, client, driver_handle).await;
+                    }
+                    error
                 },
-                _ => continue,
+                Err(err) => err,
+            };
+
+            failed_attempts += 1;
+            let delay = match delays.next() {
+                Some(delay) => delay,
+                None => Err(BlockError::Reconnect { attempts: failed_attempts, source: error })?,
+            };
+            warn!("Websocket connection to {} failed, reconnecting in {:?}: {}", redacted_host, delay, error);
+
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = tokio::time::sleep(delay) => {},
             }
         }
 
-        client.close().map_err(|source| BlockError::TendermintError { source })?;
-        driver_handle.await??;
+        trace!("Stopped streaming blocks from {}", redacted_host);
     })
 }
 
 ///
-/// Stream polled blocks from the given rpc endpoint.
+/// Stream polled blocks from the given rpc endpoint until the shutdown token is cancelled.
 ///
-pub fn poll_stream_blocks(http_rpc_host: String, poll_duration_secs: u64) -> BlockStream {
+pub fn poll_stream_blocks(
+    http_rpc_host: String,
+    options: HttpClientOptions,
+    poll_duration_secs: u64,
+    max_catch_up_blocks: u64,
+    poll_timeout: Duration,
+    shutdown: CancellationToken,
+) -> BlockStream {
     Box::pin(try_stream! {
-        let client = HttpClient::new(http_rpc_host.as_str()).map_err(|source| BlockError::Connect { source: source.into() })?;
+        let url = Url::parse(&http_rpc_host).map_err(|source| BlockError::Connect { source: source.into() })?;
+        let client = HttpRpcClient::new(&url, &options).map_err(|source| BlockError::Connect { source: source.into() })?;
+
+        let mut blocks = poll_stream_blocks_with_client(
+            client,
+            rpc::redact_url(&http_rpc_host),
+            None,
+            Duration::from_secs(poll_duration_secs),
+            max_catch_up_blocks,
+            poll_timeout,
+            shutdown,
+        );
+        while let Some(block) = blocks.next().await {
+            yield block?;
+        }
+    })
+}
+
+///
+/// The heights to fetch after polling the latest height, the latest one and at most
+/// `max_catch_up_blocks` of the heights skipped since the last one fetched.
+///
+fn heights_to_poll(
+    last_height: Option<u64>,
+    latest_height: u64,
+    max_catch_up_blocks: u64,
+) -> RangeInclusive<u64> {
+    match last_height {
+        Some(last_height) if latest_height <= last_height => RangeInclusive::new(1, 0),
+        Some(last_height) => {
+            (last_height + 1).max(latest_height.saturating_sub(max_catch_up_blocks))..=latest_height
+        }
+        None => latest_height..=latest_height,
+    }
+}
 
-        let poll_timeout_duration = Duration::from_secs(30);
+///
+/// Stream polled blocks from the given rpc client until the shutdown token is cancelled.
+///
+/// Each poll only asks for the node's `/status`, the full blocks are fetched when the latest height
+/// moved past `last_height`, including up to `max_catch_up_blocks` heights skipped since then.
+/// Each request errors after `poll_timeout_duration`.
+///
+fn poll_stream_blocks_with_client<C>(
+    client: C,
+    redacted_host: String,
+    mut last_height: Option<Height>,
+    poll_duration: Duration,
+    max_catch_up_blocks: u64,
+    poll_timeout_duration: Duration,
+    shutdown: CancellationToken,
+) -> BlockStream
+where
+    C: Client + Send + Sync + 'static,
+{
+    Box::pin(try_stream! {
         loop {
-            let block = timeout(poll_timeout_duration, rpc::get_latest_block(&client))
-                .await
-                .map_err(|_| BlockError::Timeout { timeout: poll_timeout_duration })??;
-            trace!("Polled block {} ({})", block.header().height, block.header().chain_id);
-            yield block.into();
-            tokio::time::sleep(Duration::from_secs(poll_duration_secs)).await;
+            let latest_height = tokio::select! {
+                _ = shutdown.cancelled() => break,
+                status = timeout(poll_timeout_duration, rpc::get_status(&client)) => {
+                    status.map_err(|_| BlockError::Timeout { timeout: poll_timeout_duration })??.sync_info.latest_block_height
+                },
+            };
+
+            let heights = heights_to_poll(
+                last_height.map(|height| height.value()),
+                latest_height.value(),
+                max_catch_up_blocks,
+            );
+            if heights.is_empty() {
+                trace!("Latest height {} unchanged on {}", latest_height, redacted_host);
+            }
+
+            // Yield in order, so the latest block comes after the heights skipped before it.
+            for height in heights {
+                let block = tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    block = timeout(poll_timeout_duration, rpc::get_block(&client, height as i64)) => {
+                        block.map_err(|_| BlockError::Timeout { timeout: poll_timeout_duration })??
+                    },
+                };
+                trace!("Polled block {} ({})", block.header().height, block.header().chain_id);
+                last_height = Some(block.header().height);
+                yield block.into();
+            }
+            if shutdown.is_cancelled() {
+                break;
+            }
+
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = tokio::time::sleep(poll_duration) => {},
+            }
         }
+
+        trace!("Stopped polling {}", redacted_host);
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use async_trait::async_trait;
+    use async_tungstenite::tungstenite::Message;
+    use futures::SinkExt;
+    use tendermint_rpc::{Response, SimpleRequest};
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::indexer::config::retry::RetryStrategy;
+    use crate::indexer::config::{DEFAULT_POLL_CATCH_UP_BLOCKS, DEFAULT_POLL_TIMEOUT_SECS};
+
+    #[tokio::test]
+    async fn ws_block_stream_gives_up_reconnecting() {
+        // Nothing listens on a port once its listener is dropped, so every connection is refused.
+        let address = TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+
+        let retry = RetryConfig::new(RetryStrategy::Fixed, 10, None, 3);
+        let mut stream = ws_block_stream_with_retry(
+            format!("ws://{}/websocket", address),
+            Duration::from_secs(60),
+            retry,
+            CancellationToken::new(),
+        );
+
+        let next = timeout(Duration::from_secs(5), stream.next())
+            .await
+            .expect("stream did not give up reconnecting");
+        let err = next.unwrap().unwrap_err();
+        assert!(err.to_string().contains("after 4 attempts"), "{}", err);
+        assert!(stream.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn ws_block_stream_cancel_while_reconnecting() {
+        let address = TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+
+        let shutdown = CancellationToken::new();
+        let retry = RetryConfig::new(RetryStrategy::Fixed, 60_000, None, 3);
+        let mut stream = ws_block_stream_with_retry(
+            format!("ws://{}/websocket", address),
+            Duration::from_secs(60),
+            retry,
+            shutdown.clone(),
+        );
+
+        let canceller = shutdown.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            canceller.cancel();
+        });
+
+        let next = timeout(Duration::from_secs(5), stream.next())
+            .await
+            .expect("stream did not stop after being cancelled");
+        assert!(next.is_none());
+    }
+
+    #[tokio::test]
+    async fn ws_block_stream_recv_timeout() {
+        // Accept the subscription, then never send an event.
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut websocket = async_tungstenite::tokio::accept_async(socket)
+                .await
+                .unwrap();
+            while let Some(Ok(message)) = websocket.next().await {
+                if let Message::Text(text) = message {
+                    let request: serde_json::Value = serde_json::from_str(&text).unwrap();
+                    let response = serde_json::json!({
+                        "jsonrpc": "2.0",
+                        "id": request["id"],
+                        "result": {},
+                    });
+                    websocket
+                        .send(Message::Text(response.to_string()))
+                        .await
+                        .unwrap();
+                }
+            }
+        });
+
+        // Give up on the first failure, so the timeout is surfaced.
+        let retry = RetryConfig::new(RetryStrategy::Fixed, 10, None, 0);
+        let mut stream = ws_block_stream_with_retry(
+            format!("ws://{}/websocket", address),
+            Duration::from_millis(200),
+            retry,
+            CancellationToken::new(),
+        );
+
+        let next = timeout(Duration::from_secs(5), stream.next())
+            .await
+            .expect("stream did not time out");
+        let err = next.unwrap().unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Block stream recv timed out after 200ms"),
+            "{}",
+            err
+        );
+    }
+
+    #[tokio::test]
+    async fn ws_block_stream_driver_stopped() {
+        // Accept the subscription, then drop the connection without closing it.
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut websocket = async_tungstenite::tokio::accept_async(socket)
+                .await
+                .unwrap();
+            if let Some(Ok(Message::Text(text))) = websocket.next().await {
+                let request: serde_json::Value = serde_json::from_str(&text).unwrap();
+                let response = serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": request["id"],
+                    "result": {},
+                });
+                websocket
+                    .send(Message::Text(response.to_string()))
+                    .await
+                    .unwrap();
+            }
+        });
+
+        // The recv timeout is far longer than the test waits, so the stopped driver must surface.
+        let retry = RetryConfig::new(RetryStrategy::Fixed, 10, None, 0);
+        let mut stream = ws_block_stream_with_retry(
+            format!("ws://{}/websocket", address),
+            Duration::from_secs(60),
+            retry,
+            CancellationToken::new(),
+        );
+
+        let next = timeout(Duration::from_secs(5), stream.next())
+            .await
+            .expect("stream hung after its driver stopped");
+        let err = next.unwrap().unwrap_err();
+        assert!(!err.to_string().contains("timed out"), "{}", err);
+        assert!(stream.next().await.is_none());
+    }
+
+    ///
+    /// A client on a chain stuck at a height, recording the methods it is asked for.
+    ///
+    #[derive(Clone, Default)]
+    struct StuckClient {
+        methods: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl Client for StuckClient {
+        async fn perform<R>(&self, request: R) -> Result<R::Response, tendermint_rpc::Error>
+        where
+            R: SimpleRequest,
+        {
+            let request: serde_json::Value = serde_json::from_str(&request.into_json()).unwrap();
+            self.methods
+                .lock()
+                .unwrap()
+                .push(request["method"].as_str().unwrap().to_string());
+
+            let status = serde_json::json!({
+                "node_info": {
+                    "protocol_version": { "p2p": "8", "block": "11", "app": "0" },
+                    "id": "0000000000000000000000000000000000000000",
+                    "listen_addr": "tcp://0.0.0.0:26656",
+                    "network": "uni-5",
+                    "version": "0.34.21",
+                    "channels": "40202122233038606100",
+                    "moniker": "croncat",
+                    "other": { "tx_index": "on", "rpc_address": "tcp://0.0.0.0:26657" }
+                },
+                "sync_info": {
+                    "latest_block_hash": "8130E6AA3A93B683F0465D61D1C4D3A9FF041283E5B8C40CAA1ABB659D8D10C8",
+                    "latest_app_hash": "",
+                    "latest_block_height": "5",
+                    "latest_block_time": "2022-10-12T14:16:05.123456789Z",
+                    "catching_up": false
+                },
+                "validator_info": {
+                    "address": "0000000000000000000000000000000000000000",
+                    "pub_key": {
+                        "type": "tendermint/PubKeyEd25519",
+                        "value": "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA="
+                    },
+                    "voting_power": "0"
+                }
+            });
+
+            R::Response::from_string(
+                serde_json::json!({ "jsonrpc": "2.0", "id": "", "result": status }).to_string(),
+            )
+        }
+    }
+
+    #[tokio::test]
+    async fn poll_stream_blocks_unchanged_height() {
+        let client = StuckClient::default();
+        let mut stream = poll_stream_blocks_with_client(
+            client.clone(),
+            "http://rpc.example.com".to_string(),
+            Some(5u32.into()),
+            Duration::from_millis(10),
+            DEFAULT_POLL_CATCH_UP_BLOCKS,
+            Duration::from_secs(DEFAULT_POLL_TIMEOUT_SECS),
+            CancellationToken::new(),
+        );
+
+        // The height never moves past the last seen one, so nothing is yielded.
+        assert!(timeout(Duration::from_millis(200), stream.next())
+            .await
+            .is_err());
+
+        let methods = client.methods.lock().unwrap();
+        assert!(methods.len() > 1);
+        assert!(methods.iter().all(|method| method == "status"));
+    }
+
+    #[test]
+    fn heights_to_poll_catches_up() {
+        // The first poll only fetches the latest block.
+        assert_eq!(heights_to_poll(None, 100, 10), 100..=100);
+        assert!(heights_to_poll(Some(100), 100, 10).is_empty());
+        assert_eq!(heights_to_poll(Some(100), 101, 10), 101..=101);
+        assert_eq!(heights_to_poll(Some(100), 104, 10), 101..=104);
+        // Only the most recent skipped heights are fetched, the older ones are left as a gap.
+        assert_eq!(heights_to_poll(Some(100), 150, 10), 140..=150);
+        assert_eq!(heights_to_poll(Some(100), 150, 0), 150..=150);
+    }
+
+    ///
+    /// Accept connections but never respond to them, returning the address listened on.
+    ///
+    async fn unresponsive_rpc() -> std::net::SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut sockets = vec![];
+            while let Ok((socket, _)) = listener.accept().await {
+                sockets.push(socket);
+            }
+        });
+        address
+    }
+
+    #[tokio::test]
+    async fn poll_stream_blocks_times_out() {
+        let address = unresponsive_rpc().await;
+        let poll_timeout = Duration::from_millis(100);
+        let mut stream = poll_stream_blocks(
+            format!("http://{}", address),
+            HttpClientOptions::default(),
+            3,
+            DEFAULT_POLL_CATCH_UP_BLOCKS,
+            poll_timeout,
+            CancellationToken::new(),
+        );
+
+        // The configured timeout applies, well before the default one would.
+        let err = timeout(Duration::from_secs(5), stream.next())
+            .await
+            .expect("poll did not time out")
+            .unwrap()
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<BlockError>(),
+            Some(BlockError::Timeout { timeout }) if *timeout == poll_timeout
+        ));
+    }
+
+    #[tokio::test]
+    async fn poll_stream_blocks_cancel() {
+        // The poll is still in flight when we cancel.
+        let address = unresponsive_rpc().await;
+
+        let shutdown = CancellationToken::new();
+        let mut stream = poll_stream_blocks(
+            format!("http://{}", address),
+            HttpClientOptions::default(),
+            3,
+            DEFAULT_POLL_CATCH_UP_BLOCKS,
+            Duration::from_secs(DEFAULT_POLL_TIMEOUT_SECS),
+            shutdown.clone(),
+        );
+
+        let canceller = shutdown.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            canceller.cancel();
+        });
+
+        let next = timeout(Duration::from_secs(5), stream.next())
+            .await
+            .expect("stream did not stop after being cancelled");
+        assert!(next.is_none());
+    }
+}
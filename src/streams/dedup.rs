@@ -0,0 +1,109 @@
+use std::collections::{HashSet, VecDeque};
+use std::hash::Hash;
+
+use super::block::Block;
+
+/// The number of recently seen blocks remembered, well beyond the sequencer window.
+pub const SEEN_BLOCKS_CAPACITY: usize = 4096;
+
+///
+/// Identify a block by its chain id, height and hash.
+///
+pub type BlockKey = (String, u64, String);
+
+///
+/// Get the key identifying a block.
+///
+pub fn block_key(block: &Block) -> BlockKey {
+    let header = block.header();
+    (
+        header.chain_id.to_string(),
+        header.height.value(),
+        header.hash().to_string(),
+    )
+}
+
+///
+/// Remember the most recently seen keys, forgetting the oldest once full.
+///
+#[derive(Debug)]
+pub struct SeenBlocks<K = BlockKey> {
+    capacity: usize,
+    seen: HashSet<K>,
+    order: VecDeque<K>,
+}
+
+impl<K: Clone + Eq + Hash> SeenBlocks<K> {
+    ///
+    /// Create a new set remembering at most `capacity` keys.
+    ///
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+
+        Self {
+            capacity,
+            seen: HashSet::with_capacity(capacity),
+            order: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    ///
+    /// Record a key, returning `false` if it was already seen.
+    ///
+    pub fn insert(&mut self, key: K) -> bool {
+        if self.seen.contains(&key) {
+            return false;
+        }
+
+        if self.order.len() == self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+        self.seen.insert(key.clone());
+        self.order.push_back(key);
+
+        true
+    }
+}
+
+impl Default for SeenBlocks {
+    fn default() -> Self {
+        Self::new(SEEN_BLOCKS_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(height: u64) -> BlockKey {
+        ("uni-5".to_string(), height, format!("{:064X}", height))
+    }
+
+    #[test]
+    fn seen_blocks_drops_duplicates_beyond_sequencer_window() {
+        let mut seen = SeenBlocks::default();
+        assert!(seen.insert(key(1)));
+
+        // More blocks than the sequencer's 128 entry window arrive before the duplicate.
+        for height in 2..=512 {
+            assert!(seen.insert(key(height)));
+        }
+        assert!(!seen.insert(key(1)));
+
+        // The same height with a different hash is a different block.
+        assert!(seen.insert(("uni-5".to_string(), 1, "0".repeat(64))));
+    }
+
+    #[test]
+    fn seen_blocks_forgets_oldest() {
+        let mut seen = SeenBlocks::new(2);
+        assert!(seen.insert(key(1)));
+        assert!(seen.insert(key(2)));
+        assert!(seen.insert(key(3)));
+
+        assert!(seen.insert(key(1)));
+        assert!(!seen.insert(key(3)));
+    }
+}
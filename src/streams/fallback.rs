@@ -0,0 +1,161 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_stream::stream;
+use futures::{Stream, StreamExt, TryStream, TryStreamExt};
+use tokio_util::sync::CancellationToken;
+
+///
+/// When the primary sources of a chain last produced a block, shared with its fallback sources.
+///
+#[derive(Debug, Clone)]
+pub struct SourceActivity {
+    last_block: Arc<Mutex<Instant>>,
+}
+
+impl Default for SourceActivity {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SourceActivity {
+    ///
+    /// Start tracking activity now, giving the primary sources a full timeout to produce a block.
+    ///
+    pub fn new() -> Self {
+        Self {
+            last_block: Arc::new(Mutex::new(Instant::now())),
+        }
+    }
+
+    ///
+    /// Record that a primary source produced a block.
+    ///
+    pub fn record(&self) {
+        *self.last_block.lock().unwrap() = Instant::now();
+    }
+
+    ///
+    /// How long since a primary source last produced a block.
+    ///
+    pub fn idle_for(&self) -> Duration {
+        self.last_block.lock().unwrap().elapsed()
+    }
+}
+
+///
+/// Record the activity of a primary source as it produces blocks.
+///
+pub fn track_activity<S>(
+    stream: S,
+    activity: SourceActivity,
+) -> impl Stream<Item = Result<S::Ok, S::Error>>
+where
+    S: TryStream,
+{
+    stream.inspect_ok(move |_| activity.record())
+}
+
+///
+/// Only pull from a fallback source while the primary sources have been idle for `timeout`.
+///
+/// As streams are lazy, a fallback polling source doesn't query its rpc while it is held back.
+///
+pub fn fallback_stream<S>(
+    mut stream: S,
+    activity: SourceActivity,
+    timeout: Duration,
+    shutdown: CancellationToken,
+) -> impl Stream<Item = S::Item>
+where
+    S: Stream + Unpin,
+{
+    stream! {
+        loop {
+            let idle = activity.idle_for();
+            if idle < timeout {
+                tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    _ = tokio::time::sleep(timeout - idle) => continue,
+                }
+            }
+
+            match stream.next().await {
+                Some(item) => yield item,
+                None => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::stream;
+    use tokio::time::timeout;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn fallback_stream_held_back_while_primary_active() {
+        let activity = SourceActivity::new();
+        let mut fallback = Box::pin(fallback_stream(
+            stream::iter(vec![1, 2]),
+            activity.clone(),
+            Duration::from_secs(60),
+            CancellationToken::new(),
+        ));
+
+        assert!(timeout(Duration::from_millis(100), fallback.next())
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn fallback_stream_takes_over_when_primary_idle() {
+        let activity = SourceActivity::new();
+        let mut fallback = Box::pin(fallback_stream(
+            stream::iter(vec![1, 2]),
+            activity.clone(),
+            Duration::from_millis(50),
+            CancellationToken::new(),
+        ));
+
+        let next = timeout(Duration::from_secs(5), fallback.next())
+            .await
+            .expect("fallback did not take over");
+        assert_eq!(next, Some(1));
+        assert!(activity.idle_for() >= Duration::from_millis(50));
+    }
+
+    #[tokio::test]
+    async fn fallback_stream_cancel() {
+        let shutdown = CancellationToken::new();
+        let mut fallback = Box::pin(fallback_stream(
+            stream::iter(vec![1, 2]),
+            SourceActivity::new(),
+            Duration::from_secs(60),
+            shutdown.clone(),
+        ));
+        shutdown.cancel();
+
+        let next = timeout(Duration::from_secs(5), fallback.next())
+            .await
+            .expect("fallback did not stop after being cancelled");
+        assert_eq!(next, None);
+    }
+
+    #[tokio::test]
+    async fn track_activity_records_blocks() {
+        let activity = SourceActivity::new();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(activity.idle_for() >= Duration::from_millis(50));
+
+        let mut primary = Box::pin(track_activity(
+            stream::iter(vec![Ok::<_, ()>(1)]),
+            activity.clone(),
+        ));
+        assert_eq!(primary.next().await, Some(Ok(1)));
+        assert!(activity.idle_for() < Duration::from_millis(50));
+    }
+}
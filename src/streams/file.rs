@@ -0,0 +1,137 @@
+use std::path::PathBuf;
+
+use async_stream::try_stream;
+use color_eyre::Report;
+use tokio::fs::File;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio_util::sync::CancellationToken;
+use tracing::trace;
+
+use super::block::BlockStream;
+use crate::indexer::BlockError;
+
+///
+/// Stream the blocks of a newline-delimited JSON file until its end or until the shutdown token is
+/// cancelled.
+///
+/// Each line holds a block as the `block` field of the rpc `/block` response, blank lines are
+/// skipped.
+///
+pub fn file_block_stream(path: PathBuf, shutdown: CancellationToken) -> BlockStream {
+    Box::pin(try_stream! {
+        let display_path = path.display().to_string();
+        let file = File::open(&path).await.map_err(|source| BlockError::ReadFile {
+            path: display_path.clone(),
+            source: source.into(),
+        })?;
+        let mut lines = BufReader::new(file).lines();
+        let mut line_number = 0;
+
+        loop {
+            let line = tokio::select! {
+                _ = shutdown.cancelled() => break,
+                line = lines.next_line() => line.map_err(|source| BlockError::ReadFile {
+                    path: display_path.clone(),
+                    source: source.into(),
+                })?,
+            };
+            let line = match line {
+                Some(line) => line,
+                None => break,
+            };
+            line_number += 1;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let block: tendermint::Block = serde_json::from_str(&line).map_err(|source| {
+                BlockError::InvalidFileBlock {
+                    path: display_path.clone(),
+                    line: line_number,
+                    source: Report::from(source),
+                }
+            })?;
+            trace!("Read block {} ({}) from {}", block.header().height, block.header().chain_id, display_path);
+            yield block.into();
+        }
+
+        trace!("Stopped reading {}", display_path);
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+
+    use super::*;
+    use crate::streams::fixture::test_block;
+
+    ///
+    /// A path in the temporary directory unique to this process and test.
+    ///
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "croncat-indexer-{}-{}.ndjson",
+            std::process::id(),
+            name
+        ))
+    }
+
+    #[tokio::test]
+    async fn file_block_stream_round_trip() {
+        let blocks = (1..=3).map(test_block).collect::<Vec<_>>();
+        let mut contents = String::new();
+        for block in &blocks {
+            contents.push_str(&serde_json::to_string(&block.inner).unwrap());
+            contents.push('\n');
+        }
+        // Blank lines, e.g. a trailing one, are skipped.
+        contents.push('\n');
+        let path = temp_path("round-trip");
+        tokio::fs::write(&path, contents).await.unwrap();
+
+        let read = file_block_stream(path.clone(), CancellationToken::new())
+            .try_collect::<Vec<_>>()
+            .await;
+        tokio::fs::remove_file(&path).await.unwrap();
+
+        let read = read.unwrap();
+        assert_eq!(read.len(), blocks.len());
+        for (read, block) in read.iter().zip(&blocks) {
+            assert_eq!(read.header().height, block.header().height);
+            assert_eq!(read.header().hash(), block.header().hash());
+            assert_eq!(read.last_commit(), block.last_commit());
+            assert_eq!(read.source, None);
+        }
+    }
+
+    #[tokio::test]
+    async fn file_block_stream_invalid_line() {
+        let mut contents = serde_json::to_string(&test_block(1).inner).unwrap();
+        contents.push_str("\n{\"header\": {}}\n");
+        let path = temp_path("invalid-line");
+        tokio::fs::write(&path, contents).await.unwrap();
+
+        let mut stream = file_block_stream(path.clone(), CancellationToken::new());
+        let first = stream.try_next().await;
+        let second = stream.try_next().await;
+        tokio::fs::remove_file(&path).await.unwrap();
+
+        assert_eq!(first.unwrap().unwrap().header().height.value(), 1);
+        let err = second.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<BlockError>(),
+            Some(BlockError::InvalidFileBlock { line: 2, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn file_block_stream_missing_file() {
+        let mut stream = file_block_stream(temp_path("missing"), CancellationToken::new());
+        let err = stream.try_next().await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<BlockError>(),
+            Some(BlockError::ReadFile { .. })
+        ));
+    }
+}
@@ -0,0 +1,69 @@
+use super::block::Block;
+
+/// The hash of empty data, standing in for every hash of the fixture blocks.
+pub const EMPTY_HASH: &str = "E3B0C44298FC1C149AFBF4C8996FB92427AE41E4649B934CA495991B7852B855";
+
+/// The chain of the fixture blocks.
+pub const CHAIN_ID: &str = "uni-5";
+
+///
+/// The JSON of a block without transactions at a height, as the `block` field of the rpc `/block`
+/// response.
+///
+/// Every block past the first has a last commit without signatures, edit the JSON (e.g. its
+/// `data.txs` or `last_commit.signatures`) and parse it with [`block_from_json`] for other blocks.
+///
+pub fn block_json(height: u64) -> serde_json::Value {
+    let block_id =
+        serde_json::json!({ "hash": EMPTY_HASH, "parts": { "total": 1, "hash": EMPTY_HASH } });
+    let (last_block_id, last_commit) = if height == 1 {
+        (serde_json::Value::Null, serde_json::Value::Null)
+    } else {
+        (
+            block_id.clone(),
+            serde_json::json!({
+                "height": (height - 1).to_string(),
+                "round": 0,
+                "block_id": block_id,
+                "signatures": []
+            }),
+        )
+    };
+
+    serde_json::json!({
+        "header": {
+            "version": { "block": "11", "app": "0" },
+            "chain_id": CHAIN_ID,
+            "height": height.to_string(),
+            "time": "2022-10-12T14:16:05.123456789Z",
+            "last_block_id": last_block_id,
+            "last_commit_hash": "",
+            "data_hash": "",
+            "validators_hash": EMPTY_HASH,
+            "next_validators_hash": EMPTY_HASH,
+            "consensus_hash": EMPTY_HASH,
+            "app_hash": "",
+            "last_results_hash": "",
+            "evidence_hash": "",
+            "proposer_address": "0000000000000000000000000000000000000000"
+        },
+        "data": { "txs": [] },
+        "evidence": { "evidence": [] },
+        "last_commit": last_commit
+    })
+}
+
+///
+/// Parse the JSON of a block, panicking if it isn't a valid block.
+///
+pub fn block_from_json(json: serde_json::Value) -> Block {
+    let block: tendermint::Block = serde_json::from_value(json).expect("invalid fixture block");
+    block.into()
+}
+
+///
+/// A block without transactions at a height, see [`block_json`].
+///
+pub fn test_block(height: u64) -> Block {
+    block_from_json(block_json(height))
+}
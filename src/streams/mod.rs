@@ -1 +1,7 @@
 pub mod block;
+pub mod dedup;
+pub mod fallback;
+pub mod file;
+/// Blocks shaped like the ones of the rpc, for the unit tests and the `test-fixtures` feature.
+#[cfg(any(test, feature = "test-fixtures"))]
+pub mod fixture;
@@ -0,0 +1,139 @@
+//!
+//! Query the indexed data through the api against a real Postgres started with `testcontainers`.
+//!
+//! These tests need a running docker daemon, run them with
+//! `cargo test --features api -- --ignored`.
+//!
+#![cfg(feature = "api")]
+
+use croncat_indexer::indexer::api::{
+    get_blocks, get_transactions, BlocksQuery, Pagination, SortOrder, TransactionsQuery,
+};
+use testcontainers::{clients, images::postgres::Postgres};
+
+mod common;
+
+use common::*;
+
+#[tokio::test]
+#[ignore = "requires docker"]
+async fn get_blocks_paginated_and_ordered() {
+    let docker = clients::Cli::default();
+    let node = docker.run(Postgres::default());
+    let db = connect(node.get_host_port_ipv4(5432)).await;
+
+    for height in [3, 1, 5, 2, 4] {
+        insert_block(&db, height, days_ago(1)).await;
+    }
+    insert_chain_block(&db, "juno-1", 1, days_ago(1)).await;
+
+    let query = BlocksQuery {
+        chain_id: CHAIN_ID.to_string(),
+        from: None,
+        to: None,
+    };
+    let pagination = Pagination {
+        page: 1,
+        per_page: 2,
+        order: SortOrder::Asc,
+    };
+    let page = get_blocks(&db, &query, &pagination, 100).await.unwrap();
+    assert_eq!(page.total, 5);
+    assert_eq!(
+        page.items
+            .iter()
+            .map(|block| block.height)
+            .collect::<Vec<_>>(),
+        vec![1, 2]
+    );
+
+    let pagination = Pagination {
+        page: 3,
+        ..pagination
+    };
+    let page = get_blocks(&db, &query, &pagination, 100).await.unwrap();
+    assert_eq!(
+        page.items
+            .iter()
+            .map(|block| block.height)
+            .collect::<Vec<_>>(),
+        vec![5]
+    );
+
+    // Descending within an inclusive height range.
+    let query = BlocksQuery {
+        from: Some(2),
+        to: Some(4),
+        ..query
+    };
+    let pagination = Pagination {
+        page: 1,
+        per_page: 10,
+        order: SortOrder::Desc,
+    };
+    let page = get_blocks(&db, &query, &pagination, 100).await.unwrap();
+    assert_eq!(page.total, 3);
+    assert_eq!(
+        page.items
+            .iter()
+            .map(|block| block.height)
+            .collect::<Vec<_>>(),
+        vec![4, 3, 2]
+    );
+
+    // The page size is capped.
+    let page = get_blocks(&db, &query, &pagination, 2).await.unwrap();
+    assert_eq!(page.per_page, 2);
+    assert_eq!(page.items.len(), 2);
+
+    // An empty range is rejected.
+    let query = BlocksQuery {
+        from: Some(4),
+        to: Some(2),
+        ..query
+    };
+    assert!(get_blocks(&db, &query, &pagination, 100).await.is_err());
+}
+
+#[tokio::test]
+#[ignore = "requires docker"]
+async fn get_transactions_by_hash_and_chain() {
+    let docker = clients::Cli::default();
+    let node = docker.run(Postgres::default());
+    let db = connect(node.get_host_port_ipv4(5432)).await;
+
+    let block_id = insert_block(&db, 1, days_ago(1)).await;
+    insert_transaction(&db, block_id, 1, &"A".repeat(64)).await;
+    let block_id = insert_block(&db, 2, days_ago(1)).await;
+    insert_transaction(&db, block_id, 2, &"B".repeat(64)).await;
+    let other_block_id = insert_chain_block(&db, "juno-1", 3, days_ago(1)).await;
+    insert_transaction(&db, other_block_id, 3, &"C".repeat(64)).await;
+
+    let query = TransactionsQuery {
+        hash: Some("b".repeat(64)),
+        chain_id: None,
+    };
+    let page = get_transactions(&db, &query, &Pagination::default(), 100)
+        .await
+        .unwrap();
+    assert_eq!(page.total, 1);
+    assert_eq!(page.items[0].hash, "B".repeat(64));
+    assert_eq!(page.items[0].chain_id.as_deref(), Some(CHAIN_ID));
+
+    let query = TransactionsQuery {
+        hash: None,
+        chain_id: Some(CHAIN_ID.to_string()),
+    };
+    let pagination = Pagination {
+        order: SortOrder::Desc,
+        ..Pagination::default()
+    };
+    let page = get_transactions(&db, &query, &pagination, 100)
+        .await
+        .unwrap();
+    assert_eq!(page.total, 2);
+    assert_eq!(
+        page.items.iter().map(|tx| tx.height).collect::<Vec<_>>(),
+        vec![2, 1]
+    );
+}
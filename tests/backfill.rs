@@ -0,0 +1,75 @@
+//!
+//! Track backfill jobs against a real Postgres started with `testcontainers`.
+//!
+//! These tests need a running docker daemon, run them with `cargo test -- --ignored`.
+//!
+use croncat_indexer::indexer::backfill::{
+    fail_backfill_job, finish_backfill_job, get_pending_backfill_jobs, start_backfill_job,
+    sync_backfill_jobs,
+};
+use croncat_indexer::indexer::historical::BlockRange;
+use sea_orm::DatabaseConnection;
+use testcontainers::{clients, images::postgres::Postgres};
+
+mod common;
+
+use common::*;
+
+async fn pending(db: &DatabaseConnection) -> Vec<(i64, i64, String, i32)> {
+    get_pending_backfill_jobs(db, CHAIN_ID)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|job| (job.start_height, job.end_height, job.status, job.attempts))
+        .collect()
+}
+
+#[tokio::test]
+#[ignore = "requires docker"]
+async fn backfill_jobs_follow_gaps() {
+    let docker = clients::Cli::default();
+    let node = docker.run(Postgres::default());
+    let db = connect(node.get_host_port_ipv4(5432)).await;
+
+    let ranges: Vec<BlockRange> = vec![(10, 19).into(), (20, 29).into(), (50, 59).into()];
+    sync_backfill_jobs(&db, CHAIN_ID, &ranges).await.unwrap();
+    assert_eq!(
+        pending(&db).await,
+        vec![
+            (50, 59, "pending".to_string(), 0),
+            (20, 29, "pending".to_string(), 0),
+            (10, 19, "pending".to_string(), 0),
+        ]
+    );
+
+    // A failed job is retried after the ones never attempted, with its error kept.
+    let job = get_pending_backfill_jobs(&db, CHAIN_ID).await.unwrap()[0].clone();
+    let job = start_backfill_job(&db, job).await.unwrap();
+    let job = fail_backfill_job(&db, job, "rpc is down".to_string())
+        .await
+        .unwrap();
+    assert_eq!(job.last_error.as_deref(), Some("rpc is down"));
+    assert_eq!(
+        pending(&db).await,
+        vec![
+            (20, 29, "pending".to_string(), 0),
+            (10, 19, "pending".to_string(), 0),
+            (50, 59, "failed".to_string(), 1),
+        ]
+    );
+
+    let job = get_pending_backfill_jobs(&db, CHAIN_ID).await.unwrap()[0].clone();
+    let job = start_backfill_job(&db, job).await.unwrap();
+    finish_backfill_job(&db, job).await.unwrap();
+
+    // The gap of the failed job shrank and 10-19 was filled by the live indexer.
+    let ranges: Vec<BlockRange> = vec![(55, 59).into()];
+    sync_backfill_jobs(&db, CHAIN_ID, &ranges).await.unwrap();
+    assert_eq!(pending(&db).await, vec![(55, 59, "failed".to_string(), 1)]);
+
+    // Other chains have their own queue.
+    assert!(get_pending_backfill_jobs(&db, "juno-1")
+        .await
+        .unwrap()
+        .is_empty());
+}
@@ -0,0 +1,188 @@
+//!
+//! Run the historical gap detection against a real Postgres started with `testcontainers`.
+//!
+//! These tests need a running docker daemon, run them with `cargo test -- --ignored`.
+//!
+use chrono::Duration;
+use croncat_indexer::indexer::historical::{
+    get_block_gaps, get_max_indexed_height, get_min_indexed_height, UNBOUNDED_LOOKBACK_DAYS,
+};
+use sea_orm::DatabaseConnection;
+use testcontainers::{clients, images::postgres::Postgres};
+
+mod common;
+
+use common::*;
+
+async fn gaps(db: &DatabaseConnection, lookback_in_days: i64) -> Vec<(i64, i64)> {
+    gaps_from(db, lookback_in_days, None).await
+}
+
+async fn gaps_from(
+    db: &DatabaseConnection,
+    lookback_in_days: i64,
+    start_height: Option<i64>,
+) -> Vec<(i64, i64)> {
+    get_block_gaps(db, CHAIN_ID.to_string(), lookback_in_days, start_height)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|gap| (gap.start, gap.end))
+        .collect()
+}
+
+#[tokio::test]
+#[ignore = "requires docker"]
+async fn get_block_gaps_finds_gaps() {
+    let docker = clients::Cli::default();
+    let node = docker.run(Postgres::default());
+    let db = connect(node.get_host_port_ipv4(5432)).await;
+
+    // Heights 4-5 and 8-9 are missing, 11 was skipped so 10-12 is contiguous.
+    for (height, days) in [(1, 3), (2, 3), (3, 3), (6, 2), (7, 2), (10, 1), (12, 1)] {
+        insert_block(&db, height, days_ago(days)).await;
+    }
+    insert_skipped_block(&db, 11, days_ago(1)).await;
+
+    // Another chain never contributes gaps.
+    insert_chain_block(&db, "juno-1", 5, days_ago(2)).await;
+
+    // Most recent gaps come first.
+    assert_eq!(gaps(&db, 7).await, vec![(8, 9), (4, 5)]);
+}
+
+#[tokio::test]
+#[ignore = "requires docker"]
+async fn gaps_prints_only_the_report() {
+    let docker = clients::Cli::default();
+    let node = docker.run(Postgres::default());
+    let port = node.get_host_port_ipv4(5432);
+    let db = connect(port).await;
+    for height in [1, 2, 5] {
+        insert_block(&db, height, days_ago(1)).await;
+    }
+
+    let output = run_indexer(port, &["gaps", "--chain-id", CHAIN_ID]).await;
+    assert!(output.status.success());
+
+    // The logs go to stderr, stdout only holds the report.
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines = stdout.lines().collect::<Vec<_>>();
+    assert_eq!(lines.first(), Some(&"1 gaps on uni-5 over the last 7 days"));
+    assert!(lines[1].starts_with("3-4: 2 blocks"));
+    assert_eq!(lines.last(), Some(&"2 blocks missing, 0 severe gaps"));
+    assert_eq!(lines.len(), 3);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Starting croncat-indexer"));
+}
+
+#[tokio::test]
+#[ignore = "requires docker"]
+async fn get_block_gaps_respects_lookback() {
+    let docker = clients::Cli::default();
+    let node = docker.run(Postgres::default());
+    let db = connect(node.get_host_port_ipv4(5432)).await;
+
+    // Height 12 sits just outside a 7 day lookback, height 14 just inside it.
+    insert_block(&db, 10, days_ago(10)).await;
+    insert_block(&db, 12, days_ago(7) - Duration::hours(1)).await;
+    insert_block(&db, 14, days_ago(7) + Duration::hours(1)).await;
+    insert_block(&db, 17, days_ago(1)).await;
+
+    // Only the blocks within the lookback are considered.
+    assert_eq!(gaps(&db, 7).await, vec![(15, 16)]);
+
+    // A longer lookback also finds the older gaps.
+    assert_eq!(gaps(&db, 14).await, vec![(15, 16), (13, 13), (11, 11)]);
+}
+
+#[tokio::test]
+#[ignore = "requires docker"]
+async fn get_block_gaps_unbounded_lookback() {
+    let docker = clients::Cli::default();
+    let node = docker.run(Postgres::default());
+    let db = connect(node.get_host_port_ipv4(5432)).await;
+
+    // The gap after height 10 is far outside the default lookback.
+    insert_block(&db, 10, days_ago(400)).await;
+    insert_block(&db, 12, days_ago(300)).await;
+    insert_block(&db, 14, days_ago(1)).await;
+    insert_block(&db, 16, days_ago(1)).await;
+    assert_eq!(gaps(&db, 7).await, vec![(15, 15)]);
+
+    // Without a time filter every gap is found.
+    assert_eq!(
+        gaps(&db, UNBOUNDED_LOOKBACK_DAYS).await,
+        vec![(15, 15), (13, 13), (11, 11)]
+    );
+}
+
+#[tokio::test]
+#[ignore = "requires docker"]
+async fn get_block_gaps_respects_start_height() {
+    let docker = clients::Cli::default();
+    let node = docker.run(Postgres::default());
+    let db = connect(node.get_host_port_ipv4(5432)).await;
+
+    // Heights 2-4, 6-9 and 11-11 are missing.
+    for (height, days) in [(1, 3), (5, 2), (10, 1), (12, 1)] {
+        insert_block(&db, height, days_ago(days)).await;
+    }
+    assert_eq!(gaps(&db, 7).await, vec![(11, 11), (6, 9), (2, 4)]);
+
+    // Gaps below the start height are excluded, and one spanning it is clamped.
+    assert_eq!(gaps_from(&db, 7, Some(8)).await, vec![(11, 11), (8, 9)]);
+    assert_eq!(gaps_from(&db, 7, Some(6)).await, vec![(11, 11), (6, 9)]);
+    assert_eq!(gaps_from(&db, 7, Some(12)).await, vec![]);
+}
+
+#[tokio::test]
+#[ignore = "requires docker"]
+async fn get_max_indexed_height_includes_skipped_blocks() {
+    let docker = clients::Cli::default();
+    let node = docker.run(Postgres::default());
+    let db = connect(node.get_host_port_ipv4(5432)).await;
+
+    assert_eq!(get_max_indexed_height(&db, CHAIN_ID).await.unwrap(), None);
+
+    insert_block(&db, 10, days_ago(1)).await;
+    insert_block(&db, 12, days_ago(1)).await;
+    assert_eq!(
+        get_max_indexed_height(&db, CHAIN_ID).await.unwrap(),
+        Some(12)
+    );
+
+    insert_skipped_block(&db, 13, days_ago(1)).await;
+    assert_eq!(
+        get_max_indexed_height(&db, CHAIN_ID).await.unwrap(),
+        Some(13)
+    );
+
+    // Other chains are ignored.
+    assert_eq!(get_max_indexed_height(&db, "juno-1").await.unwrap(), None);
+}
+
+#[tokio::test]
+#[ignore = "requires docker"]
+async fn get_min_indexed_height_includes_skipped_blocks() {
+    let docker = clients::Cli::default();
+    let node = docker.run(Postgres::default());
+    let db = connect(node.get_host_port_ipv4(5432)).await;
+
+    assert_eq!(get_min_indexed_height(&db, CHAIN_ID).await.unwrap(), None);
+
+    insert_block(&db, 10, days_ago(1)).await;
+    insert_block(&db, 12, days_ago(1)).await;
+    assert_eq!(
+        get_min_indexed_height(&db, CHAIN_ID).await.unwrap(),
+        Some(10)
+    );
+
+    insert_skipped_block(&db, 8, days_ago(1)).await;
+    assert_eq!(
+        get_min_indexed_height(&db, CHAIN_ID).await.unwrap(),
+        Some(8)
+    );
+
+    // Other chains are ignored.
+    assert_eq!(get_min_indexed_height(&db, "juno-1").await.unwrap(), None);
+}
@@ -0,0 +1,204 @@
+//!
+//! Index blocks against a real Postgres started with `testcontainers`.
+//!
+//! These tests need a running docker daemon, run them with
+//! `cargo test --features test-fixtures -- --ignored`.
+//!
+#![cfg(feature = "test-fixtures")]
+
+use std::path::{Path, PathBuf};
+
+use croncat_indexer::indexer::config::filter::CompiledFilterSet;
+use croncat_indexer::indexer::config::Config;
+use croncat_indexer::indexer::historical::{get_block_gaps, UNBOUNDED_LOOKBACK_DAYS};
+use croncat_indexer::indexer::index_block;
+use croncat_indexer::indexer::model::{block, transaction};
+use croncat_indexer::indexer::rpc::{HttpClientOptions, RpcPool};
+use croncat_indexer::indexer::sink::Sinks;
+use croncat_indexer::indexer::system::{polling_rpc_pool, run_with};
+use croncat_indexer::streams::block::Block;
+use croncat_indexer::streams::fixture::{block_from_json, block_json};
+use sea_orm::{DatabaseConnection, EntityTrait, PaginatorTrait, QueryOrder};
+use testcontainers::{clients, images::postgres::Postgres};
+use tokio_util::sync::CancellationToken;
+use url::Url;
+
+mod common;
+
+use common::*;
+
+///
+/// The first block of the chain, holding a single transaction.
+///
+fn block_with_transaction() -> Block {
+    let mut json = block_json(1);
+    json["data"]["txs"] = serde_json::json!(["Y3JvbmNhdA=="]);
+    block_from_json(json)
+}
+
+#[tokio::test]
+#[ignore = "requires docker"]
+async fn index_block_without_transactions() {
+    let docker = clients::Cli::default();
+    let node = docker.run(Postgres::default());
+    let db = connect(node.get_host_port_ipv4(5432)).await;
+
+    let config: Config = serde_yaml::from_str(indoc::indoc! {r#"
+        name: test
+        chain_id: uni-5
+        sources: []
+        filters: []
+        index-transactions: false
+    "#})
+    .unwrap();
+    config.validate().unwrap();
+    let filter_set = CompiledFilterSet::from_config(&config).unwrap();
+
+    // Nothing listens there, so indexing fails if the transactions are fetched.
+    let url = Url::parse("http://127.0.0.1:1").unwrap();
+    let rpc_pool = RpcPool::from_urls([(&url, HttpClientOptions::default())]).unwrap();
+
+    index_block(
+        &config,
+        &db,
+        &rpc_pool,
+        &Sinks::default(),
+        &filter_set,
+        block_with_transaction(),
+    )
+    .await
+    .unwrap();
+
+    let stored = block::Entity::find_by_id((1, CHAIN_ID.to_string()))
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(stored.num_txs, 1);
+    assert_eq!(transaction::Entity::find().count(&db).await.unwrap(), 0);
+
+    // The block counts as indexed for the gap detector.
+    insert_block(&db, 2, days_ago(0)).await;
+    let gaps = get_block_gaps(&db, CHAIN_ID.to_string(), UNBOUNDED_LOOKBACK_DAYS, None)
+        .await
+        .unwrap();
+    assert!(gaps.is_empty());
+}
+
+///
+/// Write the fixture blocks at the given heights to a newline-delimited JSON file, in that order.
+///
+async fn write_block_file(name: &str, heights: &[u64]) -> PathBuf {
+    let path = std::env::temp_dir().join(format!(
+        "croncat-indexer-{}-{}.ndjson",
+        std::process::id(),
+        name
+    ));
+    let mut contents = String::new();
+    for height in heights {
+        contents.push_str(&block_json(*height).to_string());
+        contents.push('\n');
+    }
+    tokio::fs::write(&path, contents).await.unwrap();
+    path
+}
+
+///
+/// A configuration indexing the blocks of a file without any rpc, followed by the given settings.
+///
+fn file_config(path: &Path, settings: &str) -> Config {
+    let config: Config = serde_yaml::from_str(&format!(
+        indoc::indoc! {r#"
+            name: test
+            chain_id: uni-5
+            sources:
+              - name: archive
+                type: file
+                url: {}
+            filters: []
+            index-transactions: false
+            {}
+        "#},
+        Url::from_file_path(path).unwrap(),
+        settings
+    ))
+    .unwrap();
+    config.validate().unwrap();
+    config
+}
+
+///
+/// Run a configuration until it stops by itself, failing after 30 seconds.
+///
+async fn run_until_stopped(config: &Config, shutdown: &CancellationToken, db: &DatabaseConnection) {
+    // Without a polling source nothing is queried.
+    let rpc_pool = polling_rpc_pool(config).unwrap();
+    assert!(rpc_pool.is_empty());
+
+    tokio::time::timeout(
+        std::time::Duration::from_secs(30),
+        run_with(config, shutdown, db.clone(), rpc_pool),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+}
+
+///
+/// The stored blocks, by height.
+///
+async fn stored_blocks(db: &DatabaseConnection) -> Vec<block::Model> {
+    block::Entity::find()
+        .order_by_asc(block::Column::Height)
+        .all(db)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+#[ignore = "requires docker"]
+async fn run_with_file_source() {
+    let docker = clients::Cli::default();
+    let node = docker.run(Postgres::default());
+    let db = connect(node.get_host_port_ipv4(5432)).await;
+
+    // Out of order, within the sequencer's window, the run ends with the file.
+    let path = write_block_file("run-with", &[1, 3, 2]).await;
+    let config = file_config(&path, "");
+    run_until_stopped(&config, &CancellationToken::new(), &db).await;
+    tokio::fs::remove_file(&path).await.unwrap();
+
+    let stored = stored_blocks(&db).await;
+    let heights = stored.iter().map(|block| block.height).collect::<Vec<_>>();
+    assert_eq!(heights, vec![1, 2, 3]);
+    assert!(stored
+        .iter()
+        .all(|block| block.source.as_deref() == Some("file-archive")));
+}
+
+#[tokio::test]
+#[ignore = "requires docker"]
+async fn run_with_stops_at_stop_height() {
+    let docker = clients::Cli::default();
+    let node = docker.run(Postgres::default());
+    let db = connect(node.get_host_port_ipv4(5432)).await;
+
+    let path = write_block_file("stop-height", &[1, 2, 3, 4, 5, 6]).await;
+    let config = file_config(&path, "stop-height: 3");
+    let shutdown = CancellationToken::new();
+    run_until_stopped(&config, &shutdown, &db).await;
+
+    // Only the run's own token is cancelled, the other tasks of `run_all` keep running.
+    assert!(!shutdown.is_cancelled());
+    let heights = stored_blocks(&db)
+        .await
+        .iter()
+        .map(|block| block.height)
+        .collect::<Vec<_>>();
+    assert_eq!(heights, vec![1, 2, 3]);
+
+    // Once the stop height is indexed, a restart returns right away.
+    run_until_stopped(&config, &shutdown, &db).await;
+    tokio::fs::remove_file(&path).await.unwrap();
+    assert_eq!(stored_blocks(&db).await.len(), 3);
+}
@@ -0,0 +1,88 @@
+//!
+//! Notify Postgres listeners of indexed blocks against a real Postgres started with `testcontainers`.
+//!
+//! These tests need a running docker daemon, run them with `cargo test -- --ignored`.
+//!
+use std::time::Duration;
+
+use croncat_indexer::indexer::config::Config;
+use croncat_indexer::indexer::model::block;
+use croncat_indexer::indexer::{save_block, BLOCK_INDEXED_CHANNEL};
+use sea_orm::prelude::Uuid;
+use sea_orm::sqlx::postgres::PgListener;
+use sea_orm::Set;
+use testcontainers::{clients, images::postgres::Postgres};
+use tokio::time::timeout;
+
+mod common;
+
+use common::*;
+
+fn block(height: i64) -> block::ActiveModel {
+    block::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        height: Set(height),
+        time: Set(days_ago(0)),
+        chain_id: Set(CHAIN_ID.to_string()),
+        hash: Set(format!("{:064X}", height)),
+        num_txs: Set(0),
+        proposer_address: Set(None),
+        app_hash: Set(None),
+        last_commit_hash: Set(None),
+        total_gas_used: Set(0),
+        total_gas_wanted: Set(0),
+        source: Set(None),
+        last_commit_signatures: Set(None),
+        last_commit_validators: Set(None),
+    }
+}
+
+#[tokio::test]
+#[ignore = "requires docker"]
+async fn save_block_notifies_listeners() {
+    let docker = clients::Cli::default();
+    let node = docker.run(Postgres::default());
+    let port = node.get_host_port_ipv4(5432);
+    let db = connect(port).await;
+
+    let mut listener = PgListener::connect(&database_url(port)).await.unwrap();
+    listener.listen(BLOCK_INDEXED_CHANNEL).await.unwrap();
+
+    let mut config: Config = serde_yaml::from_str(indoc::indoc! {r#"
+        name: test
+        chain_id: uni-5
+        sources: []
+        filters: []
+        notify-blocks: true
+    "#})
+    .unwrap();
+
+    save_block(&config, &db, block(10), vec![])
+        .await
+        .unwrap()
+        .unwrap();
+    let notification = timeout(Duration::from_secs(5), listener.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(notification.payload(), "uni-5:10");
+
+    // A block that was already indexed rolls back, so nothing is sent for it.
+    assert!(save_block(&config, &db, block(10), vec![])
+        .await
+        .unwrap()
+        .is_none());
+    assert!(timeout(Duration::from_millis(500), listener.recv())
+        .await
+        .is_err());
+
+    // Nothing is sent without the flag either.
+    config.notify_blocks = false;
+    save_block(&config, &db, block(11), vec![])
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(timeout(Duration::from_millis(500), listener.recv())
+        .await
+        .is_err());
+}
@@ -0,0 +1,58 @@
+//!
+//! Prune old blocks against a real Postgres started with `testcontainers`.
+//!
+//! These tests need a running docker daemon, run them with `cargo test -- --ignored`.
+//!
+use croncat_indexer::indexer::model::{block, block_signature, skipped_block, transaction};
+use croncat_indexer::indexer::prune::{prune_blocks, PruneStats};
+use sea_orm::{EntityTrait, PaginatorTrait};
+use testcontainers::{clients, images::postgres::Postgres};
+
+mod common;
+
+use common::*;
+
+#[tokio::test]
+#[ignore = "requires docker"]
+async fn prune_blocks_older_than_cutoff() {
+    let docker = clients::Cli::default();
+    let node = docker.run(Postgres::default());
+    let db = connect(node.get_host_port_ipv4(5432)).await;
+
+    let old_block_id = insert_block(&db, 1, days_ago(40)).await;
+    insert_transaction(&db, old_block_id, 1, &"A".repeat(64)).await;
+    insert_block(&db, 2, days_ago(35)).await;
+    insert_skipped_block(&db, 3, days_ago(35)).await;
+    for validator_address in ["1".repeat(40), "2".repeat(40)] {
+        insert_block_signature(&db, 1, &validator_address, days_ago(40)).await;
+        insert_block_signature(&db, 3, &validator_address, days_ago(1)).await;
+    }
+    let new_block_id = insert_block(&db, 4, days_ago(1)).await;
+    insert_transaction(&db, new_block_id, 4, &"B".repeat(64)).await;
+    let other_block_id = insert_chain_block(&db, "juno-1", 100, days_ago(40)).await;
+    insert_transaction(&db, other_block_id, 100, &"C".repeat(64)).await;
+
+    // A batch size of 1 exercises the batching.
+    let stats = prune_blocks(&db, CHAIN_ID, days_ago(30), 1).await.unwrap();
+
+    assert_eq!(
+        stats,
+        PruneStats {
+            blocks: 2,
+            transactions: 1,
+            skipped_blocks: 1,
+            block_signatures: 2,
+        }
+    );
+    // The recent block and the other chain are kept.
+    assert_eq!(block::Entity::find().count(&db).await.unwrap(), 2);
+    assert_eq!(transaction::Entity::find().count(&db).await.unwrap(), 2);
+    assert_eq!(skipped_block::Entity::find().count(&db).await.unwrap(), 0);
+    assert_eq!(block_signature::Entity::find().count(&db).await.unwrap(), 2);
+
+    // Nothing is left to prune.
+    assert_eq!(
+        prune_blocks(&db, CHAIN_ID, days_ago(30), 1).await.unwrap(),
+        PruneStats::default()
+    );
+}
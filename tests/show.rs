@@ -0,0 +1,70 @@
+//!
+//! Read back stored blocks against a real Postgres started with `testcontainers`.
+//!
+//! These tests need a running docker daemon, run them with `cargo test -- --ignored`.
+//!
+use chrono::Timelike;
+use croncat_indexer::indexer::show::{get_stored_block, StoredBlock};
+use testcontainers::{clients, images::postgres::Postgres};
+
+mod common;
+
+use common::*;
+
+#[tokio::test]
+#[ignore = "requires docker"]
+async fn show_stored_block() {
+    let docker = clients::Cli::default();
+    let node = docker.run(Postgres::default());
+    let db = connect(node.get_host_port_ipv4(5432)).await;
+
+    // Drop the sub-second part so the time round trips through Postgres.
+    let time = days_ago(0).with_nanosecond(0).unwrap();
+    let block_id = insert_block(&db, 10, time).await;
+    insert_transaction(&db, block_id, 10, &"B".repeat(64)).await;
+    insert_transaction(&db, block_id, 10, &"A".repeat(64)).await;
+    let other_block_id = insert_chain_block(&db, "juno-1", 10, time).await;
+    insert_transaction(&db, other_block_id, 10, &"C".repeat(64)).await;
+
+    let block = get_stored_block(&db, CHAIN_ID, 10).await.unwrap().unwrap();
+    assert_eq!(block.id, block_id.to_string());
+    assert_eq!(block.chain_id, CHAIN_ID);
+    assert_eq!(block.time, time);
+    assert_eq!(block.hash, format!("{:064X}", 10));
+
+    // Only the transactions of the block, ordered by hash.
+    let hashes = block
+        .transactions
+        .iter()
+        .map(|transaction| transaction.hash.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(hashes, vec!["A".repeat(64), "B".repeat(64)]);
+    assert!(block
+        .transactions
+        .iter()
+        .all(|transaction| transaction.block_id == block.id));
+
+    let json = serde_json::to_value(&block).unwrap();
+    assert_eq!(json["height"], 10);
+    assert_eq!(json["transactions"][0]["events"], serde_json::json!([]));
+
+    assert_eq!(get_stored_block(&db, CHAIN_ID, 11).await.unwrap(), None);
+}
+
+#[tokio::test]
+#[ignore = "requires docker"]
+async fn show_prints_only_json() {
+    let docker = clients::Cli::default();
+    let node = docker.run(Postgres::default());
+    let port = node.get_host_port_ipv4(5432);
+    let db = connect(port).await;
+    let block_id = insert_block(&db, 10, days_ago(0)).await;
+
+    let output = run_indexer(port, &["show", "--chain-id", CHAIN_ID, "--height", "10"]).await;
+    assert!(output.status.success());
+
+    // The logs go to stderr, stdout can be piped into a JSON parser as is.
+    let block: StoredBlock = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(block.id, block_id.to_string());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Starting croncat-indexer"));
+}
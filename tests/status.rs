@@ -0,0 +1,136 @@
+//!
+//! Compute the indexing status aggregates against a real Postgres started with `testcontainers`.
+//!
+//! These tests need a running docker daemon, run them with `cargo test -- --ignored`.
+//!
+use chrono::{Duration, NaiveDateTime, Timelike};
+use croncat_indexer::indexer::status::{
+    get_block_time_range, get_chain_status, get_open_gaps, get_total_blocks,
+    get_total_transactions, BlockTimeRange, ChainStatus,
+};
+use sea_orm::DatabaseConnection;
+use testcontainers::{clients, images::postgres::Postgres};
+
+mod common;
+
+use common::*;
+
+///
+/// Seed blocks 1-3, 6 and 8 (skipping 7) with two transactions, plus a block on another chain.
+///
+async fn seed(db: &DatabaseConnection) -> (NaiveDateTime, NaiveDateTime) {
+    // Drop the sub-second part so the times round trip through Postgres.
+    let now = days_ago(0).with_nanosecond(0).unwrap();
+    let oldest = now - Duration::days(3);
+
+    let first_block_id = insert_block(db, 1, oldest).await;
+    insert_block(db, 2, now - Duration::days(2)).await;
+    let third_block_id = insert_block(db, 3, now - Duration::days(2)).await;
+    insert_block(db, 6, now - Duration::days(1)).await;
+    insert_skipped_block(db, 7, now - Duration::days(1)).await;
+    insert_block(db, 8, now).await;
+
+    insert_transaction(db, first_block_id, 1, &"A".repeat(64)).await;
+    insert_transaction(db, third_block_id, 3, &"B".repeat(64)).await;
+
+    let other_block_id = insert_chain_block(db, "juno-1", 100, now).await;
+    insert_transaction(db, other_block_id, 100, &"C".repeat(64)).await;
+
+    (oldest, now)
+}
+
+#[tokio::test]
+#[ignore = "requires docker"]
+async fn status_total_blocks() {
+    let docker = clients::Cli::default();
+    let node = docker.run(Postgres::default());
+    let db = connect(node.get_host_port_ipv4(5432)).await;
+
+    assert_eq!(get_total_blocks(&db, CHAIN_ID).await.unwrap(), 0);
+
+    seed(&db).await;
+
+    // Skipped blocks aren't indexed blocks.
+    assert_eq!(get_total_blocks(&db, CHAIN_ID).await.unwrap(), 5);
+    assert_eq!(get_total_blocks(&db, "juno-1").await.unwrap(), 1);
+}
+
+#[tokio::test]
+#[ignore = "requires docker"]
+async fn status_total_transactions() {
+    let docker = clients::Cli::default();
+    let node = docker.run(Postgres::default());
+    let db = connect(node.get_host_port_ipv4(5432)).await;
+
+    assert_eq!(get_total_transactions(&db, CHAIN_ID).await.unwrap(), 0);
+
+    seed(&db).await;
+
+    assert_eq!(get_total_transactions(&db, CHAIN_ID).await.unwrap(), 2);
+    assert_eq!(get_total_transactions(&db, "juno-1").await.unwrap(), 1);
+}
+
+#[tokio::test]
+#[ignore = "requires docker"]
+async fn status_open_gaps() {
+    let docker = clients::Cli::default();
+    let node = docker.run(Postgres::default());
+    let db = connect(node.get_host_port_ipv4(5432)).await;
+
+    assert_eq!(get_open_gaps(&db, CHAIN_ID).await.unwrap(), 0);
+
+    seed(&db).await;
+
+    // Heights 4-5 are missing, 7 was skipped so 6-8 is contiguous.
+    assert_eq!(get_open_gaps(&db, CHAIN_ID).await.unwrap(), 1);
+    assert_eq!(get_open_gaps(&db, "juno-1").await.unwrap(), 0);
+}
+
+#[tokio::test]
+#[ignore = "requires docker"]
+async fn status_block_time_range() {
+    let docker = clients::Cli::default();
+    let node = docker.run(Postgres::default());
+    let db = connect(node.get_host_port_ipv4(5432)).await;
+
+    assert_eq!(
+        get_block_time_range(&db, CHAIN_ID).await.unwrap(),
+        BlockTimeRange {
+            oldest: None,
+            newest: None
+        }
+    );
+
+    let (oldest, newest) = seed(&db).await;
+
+    assert_eq!(
+        get_block_time_range(&db, CHAIN_ID).await.unwrap(),
+        BlockTimeRange {
+            oldest: Some(oldest),
+            newest: Some(newest)
+        }
+    );
+}
+
+#[tokio::test]
+#[ignore = "requires docker"]
+async fn status_chain_status() {
+    let docker = clients::Cli::default();
+    let node = docker.run(Postgres::default());
+    let db = connect(node.get_host_port_ipv4(5432)).await;
+
+    let (oldest, newest) = seed(&db).await;
+
+    assert_eq!(
+        get_chain_status(&db, CHAIN_ID).await.unwrap(),
+        ChainStatus {
+            chain_id: CHAIN_ID.to_string(),
+            max_indexed_height: Some(8),
+            total_blocks: 5,
+            total_transactions: 2,
+            open_gaps: 1,
+            oldest_block_time: Some(oldest),
+            newest_block_time: Some(newest),
+        }
+    );
+}